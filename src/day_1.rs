@@ -1,4 +1,6 @@
-type Int = u16;
+use std::collections::VecDeque;
+
+type Int = u64;
 
 fn str_to_int(s: &[u8]) -> Option<u8> {
     let b = s[0];
@@ -44,6 +46,174 @@ fn str_to_int(s: &[u8]) -> Option<u8> {
     None
 }
 
+/// Word to digit table, matched with an Aho-Corasick automaton so that overlapping words
+/// like "twone" are all found. ASCII digits are always recognized.
+#[derive(Debug, Clone)]
+pub struct Vocabulary {
+    // complete transition table of the automaton, state 0 is the root
+    transitions: Vec<[u32; 256]>,
+    // (word length, digit) of every word ending in this state
+    outputs: Vec<Vec<(usize, u8)>>,
+}
+
+impl Default for Vocabulary {
+    fn default() -> Self {
+        Self::english()
+    }
+}
+
+impl Vocabulary {
+    pub fn new<'a>(words: impl IntoIterator<Item = (&'a str, u8)>) -> Self {
+        let mut transitions = vec![[0u32; 256]];
+        let mut outputs = vec![vec![]];
+
+        // build the trie, 0 is used as "no child" as the root can't be a child
+        for (word, digit) in words {
+            assert!(digit < 10, "{word:?} isn't mapped to a digit");
+            assert!(!word.is_empty(), "empty word for digit {digit}");
+            let mut state = 0;
+            for b in word.bytes() {
+                if transitions[state][b as usize] == 0 {
+                    transitions.push([0; 256]);
+                    outputs.push(vec![]);
+                    transitions[state][b as usize] = transitions.len() as u32 - 1;
+                }
+                state = transitions[state][b as usize] as usize;
+            }
+            outputs[state].push((word.len(), digit));
+        }
+
+        // breadth-first, replace missing children by the transition of the failure state
+        let mut fail = vec![0usize; transitions.len()];
+        let mut queue = VecDeque::new();
+        for child in transitions[0] {
+            if child != 0 {
+                queue.push_back(child as usize);
+            }
+        }
+        while let Some(state) = queue.pop_front() {
+            let suffix_outputs = outputs[fail[state]].clone();
+            outputs[state].extend(suffix_outputs);
+            let fail_transitions = transitions[fail[state]];
+            for (b, child) in transitions[state].iter_mut().enumerate() {
+                if *child != 0 {
+                    fail[*child as usize] = fail_transitions[b] as usize;
+                    queue.push_back(*child as usize);
+                } else {
+                    *child = fail_transitions[b];
+                }
+            }
+        }
+        Self {
+            transitions,
+            outputs,
+        }
+    }
+
+    pub fn english() -> Self {
+        Self::new([
+            ("zero", 0),
+            ("one", 1),
+            ("two", 2),
+            ("three", 3),
+            ("four", 4),
+            ("five", 5),
+            ("six", 6),
+            ("seven", 7),
+            ("eight", 8),
+            ("nine", 9),
+        ])
+    }
+
+    /// Digits with no spelled word, as in part1
+    pub fn digits_only() -> Self {
+        Self::new([])
+    }
+
+    /// First and last digits of the line, by starting position. When several words start at
+    /// the same position, the longest wins.
+    pub fn line_report(&self, line: &[u8]) -> Option<LineReport> {
+        // (digit, length)
+        let mut first: Option<(Digit, usize)> = None;
+        let mut last: Option<(Digit, usize)> = None;
+        let mut update = |digit: Digit, len: usize| {
+            if first.is_none_or(|(d, l)| {
+                digit.offset < d.offset || (digit.offset == d.offset && len > l)
+            }) {
+                first = Some((digit, len));
+            }
+            if last.is_none_or(|(d, l)| {
+                digit.offset > d.offset || (digit.offset == d.offset && len > l)
+            }) {
+                last = Some((digit, len));
+            }
+        };
+
+        let mut state = 0;
+        for (i, b) in line.iter().enumerate() {
+            if b.is_ascii_digit() {
+                let digit = Digit {
+                    value: b - b'0',
+                    offset: i,
+                    spelled: false,
+                };
+                update(digit, 1);
+            }
+            state = self.transitions[state][*b as usize] as usize;
+            for (len, value) in self.outputs[state].iter() {
+                let digit = Digit {
+                    value: *value,
+                    offset: i + 1 - len,
+                    spelled: true,
+                };
+                update(digit, *len);
+            }
+        }
+        Some(LineReport {
+            first: first?.0,
+            last: last?.0,
+        })
+    }
+}
+
+/// A digit found in a line, `offset` is the index of its first byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Digit {
+    pub value: u8,
+    pub offset: usize,
+    pub spelled: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineReport {
+    pub first: Digit,
+    pub last: Digit,
+}
+
+impl LineReport {
+    pub fn value(&self) -> Int {
+        (self.first.value * 10 + self.last.value) as Int
+    }
+}
+
+/// Same as part2, with any vocabulary
+pub fn calibration_sum(input: &[u8], vocabulary: &Vocabulary) -> Int {
+    input
+        .split(|b| b == &b'\n')
+        .filter_map(|line| vocabulary.line_report(line))
+        .map(|report| report.value())
+        .sum()
+}
+
+/// Digits found on each line, None for the lines without any digit
+pub fn calibration_report(input: &[u8], vocabulary: &Vocabulary) -> Vec<Option<LineReport>> {
+    let input = input.strip_suffix(b"\n").unwrap_or(input);
+    input
+        .split(|b| b == &b'\n')
+        .map(|line| vocabulary.line_report(line))
+        .collect()
+}
+
 pub fn generator(input: &[u8]) -> &[u8] {
     input
 }
@@ -108,5 +278,113 @@ mod tests {
             7pqrstsixteen"
             .as_bytes();
         assert_eq!(part2(generator(example2)), 281);
+        assert_eq!(calibration_sum(example2, &Vocabulary::english()), 281);
+    }
+
+    #[test]
+    fn test_vocabulary() {
+        let english = Vocabulary::default();
+        assert_eq!(calibration_sum(b"twone", &english), 21);
+        assert_eq!(calibration_sum(b"xoneightx", &english), 18);
+        assert_eq!(calibration_sum(b"abc", &english), 0);
+        assert_eq!(
+            calibration_sum(include_bytes!("../inputs/day_1.txt"), &english),
+            54925
+        );
+
+        let french = Vocabulary::new([
+            ("un", 1),
+            ("deux", 2),
+            ("trois", 3),
+            ("quatre", 4),
+            ("cinq", 5),
+            ("six", 6),
+            ("sept", 7),
+            ("huit", 8),
+            ("neuf", 9),
+        ]);
+        assert_eq!(
+            calibration_sum(b"septrois\nxdeux4un\ncinq", &french),
+            73 + 21 + 55
+        );
+
+        let german = Vocabulary::new([
+            ("eins", 1),
+            ("zwei", 2),
+            ("drei", 3),
+            ("vier", 4),
+            ("f\u{fc}nf", 5),
+            ("sechs", 6),
+            ("sieben", 7),
+            ("acht", 8),
+            ("neun", 9),
+        ]);
+        assert_eq!(
+            calibration_sum("zweins\nf\u{fc}nfacht".as_bytes(), &german),
+            21 + 58
+        );
+
+        // tokens sharing a prefix: the longest one starting at a position wins
+        let custom = Vocabulary::new([("ab", 1), ("abc", 2), ("bcd", 3)]);
+        assert_eq!(calibration_sum(b"abcd", &custom), 23);
+        assert_eq!(calibration_sum(b"xab", &custom), 11);
+    }
+
+    #[test]
+    fn test_report() {
+        let example = "xtwone3four\nabc\n7pqrstsixteen\n".as_bytes();
+        let spelled = |value, offset| Digit {
+            value,
+            offset,
+            spelled: true,
+        };
+        let digit = |value, offset| Digit {
+            value,
+            offset,
+            spelled: false,
+        };
+
+        let report = calibration_report(example, &Vocabulary::english());
+        assert_eq!(
+            report,
+            vec![
+                Some(LineReport {
+                    first: spelled(2, 1),
+                    last: spelled(4, 7),
+                }),
+                None,
+                Some(LineReport {
+                    first: digit(7, 0),
+                    last: spelled(6, 6),
+                }),
+            ]
+        );
+        assert_eq!(report[0].unwrap().value(), 24);
+
+        let report = calibration_report(example, &Vocabulary::digits_only());
+        assert_eq!(
+            report[0],
+            Some(LineReport {
+                first: digit(3, 6),
+                last: digit(3, 6),
+            })
+        );
+        assert_eq!(
+            calibration_sum(example, &Vocabulary::digits_only()),
+            33 + 77
+        );
+        assert_eq!(
+            calibration_sum(
+                include_bytes!("../inputs/day_1.txt"),
+                &Vocabulary::digits_only()
+            ),
+            55172
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "empty word")]
+    fn test_empty_word() {
+        Vocabulary::new([("one", 1), ("", 2)]);
     }
 }
@@ -1,9 +1,11 @@
-use std::{fmt::Display, str::from_utf8};
+use std::{
+    fmt::{Display, Write},
+    str::from_utf8,
+};
 
 use ahash::HashSet;
-use arrayvec::ArrayVec;
 
-const MAP_BORDER: usize = 140;
+use crate::helper::geometry::interior_points;
 
 // | is a vertical pipe connecting north and south.
 // - is a horizontal pipe connecting east and west.
@@ -11,7 +13,7 @@ const MAP_BORDER: usize = 140;
 // J is a 90-degree bend connecting north and west.
 // 7 is a 90-degree bend connecting south and west.
 // F is a 90-degree bend connecting south and east.
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Pipe {
     Vertical,
     Horizontal,
@@ -37,14 +39,76 @@ impl Display for Pipe {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
-pub enum Origin {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
     North,
     South,
     East,
     West,
 }
 
+const DIRECTIONS: [Direction; 4] = [
+    Direction::North,
+    Direction::South,
+    Direction::East,
+    Direction::West,
+];
+
+impl Direction {
+    fn opposite(self) -> Self {
+        match self {
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::East => Direction::West,
+            Direction::West => Direction::East,
+        }
+    }
+
+    /// Neighbour of `position` in this direction, if it is on the map
+    fn step(self, position: (usize, usize), map: &Map) -> Option<(usize, usize)> {
+        let (i, j) = position;
+        let next = match self {
+            Direction::North => (i.checked_sub(1)?, j),
+            Direction::South => (i + 1, j),
+            Direction::East => (i, j + 1),
+            Direction::West => (i, j.checked_sub(1)?),
+        };
+        map.get(next.0)?.get(next.1)?;
+        Some(next)
+    }
+}
+
+impl Pipe {
+    fn connections(self) -> Option<[Direction; 2]> {
+        match self {
+            Pipe::Vertical => Some([Direction::North, Direction::South]),
+            Pipe::Horizontal => Some([Direction::East, Direction::West]),
+            Pipe::NorthEast => Some([Direction::North, Direction::East]),
+            Pipe::NorthWest => Some([Direction::North, Direction::West]),
+            Pipe::SouthWest => Some([Direction::South, Direction::West]),
+            Pipe::SouthEast => Some([Direction::South, Direction::East]),
+            Pipe::None => None,
+        }
+    }
+
+    fn connecting(a: Direction, b: Direction) -> Self {
+        [
+            Pipe::Vertical,
+            Pipe::Horizontal,
+            Pipe::NorthEast,
+            Pipe::NorthWest,
+            Pipe::SouthWest,
+            Pipe::SouthEast,
+        ]
+        .into_iter()
+        .find(|p| {
+            let c = p.connections().unwrap();
+            c.contains(&a) && c.contains(&b)
+        })
+        .unwrap()
+    }
+}
+
 impl From<u8> for Pipe {
     fn from(value: u8) -> Self {
         match value {
@@ -54,35 +118,84 @@ impl From<u8> for Pipe {
             b'J' => Pipe::NorthWest,
             b'7' => Pipe::SouthWest,
             b'F' => Pipe::SouthEast,
-            b'S' => Pipe::Vertical,
             _ => Pipe::None,
         }
     }
 }
 
-type Map = ArrayVec<ArrayVec<Pipe, MAP_BORDER>, MAP_BORDER>;
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoopError {
+    NoStart,
+    /// No pipe at S closes a loop
+    Open,
+    /// Several pipes at S close a loop
+    Ambiguous(Vec<Pipe>),
+}
+
+impl Display for LoopError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoopError::NoStart => write!(f, "no S tile in the map"),
+            LoopError::Open => write!(f, "the pipes at S don't form a closed loop"),
+            LoopError::Ambiguous(pipes) => {
+                write!(f, "several pipes at S close a loop:")?;
+                pipes.iter().try_for_each(|p| write!(f, " {p}"))
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoopError {}
 
-type Type = ((u8, u8), Map);
+type Map = Vec<Vec<Pipe>>;
 
-pub fn generator(input: &[u8]) -> Type {
-    let input = from_utf8(input).unwrap();
+type Type = ((usize, usize), Map);
 
-    let map = input
+/// Parse the map and replace S by the only pipe closing a loop through it
+pub fn parse(input: &str) -> Result<Type, LoopError> {
+    let mut map = input
         .lines()
-        .map(|l| l.bytes().map(Pipe::from).collect())
-        .collect();
-    let (i, start_line) = input
+        .map(|l| l.bytes().map(Pipe::from).collect::<Vec<_>>())
+        .collect::<Map>();
+    let start = input
         .lines()
         .enumerate()
-        .find(|(_, l)| l.contains('S'))
-        .unwrap();
-    let j = start_line
-        .bytes()
-        .enumerate()
-        .find(|(_, e)| *e == b'S')
-        .unwrap()
-        .0;
-    ((i as u8, j as u8), map)
+        .find_map(|(i, l)| Some((i, l.bytes().position(|e| e == b'S')?)))
+        .ok_or(LoopError::NoStart)?;
+
+    // directions where the neighbour has a pipe going back to S
+    let connected = DIRECTIONS
+        .into_iter()
+        .filter(|d| {
+            d.step(start, &map).is_some_and(|(i, j)| {
+                map[i][j]
+                    .connections()
+                    .is_some_and(|c| c.contains(&d.opposite()))
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut closing = vec![];
+    for (k, a) in connected.iter().enumerate() {
+        for b in connected[k + 1..].iter() {
+            let pipe = Pipe::connecting(*a, *b);
+            map[start.0][start.1] = pipe;
+            if walk_loop(&start, &map).is_ok() {
+                closing.push(pipe);
+            }
+        }
+    }
+    match closing[..] {
+        [] => Err(LoopError::Open),
+        [pipe] => {
+            map[start.0][start.1] = pipe;
+            Ok((start, map))
+        }
+        _ => Err(LoopError::Ambiguous(closing)),
+    }
+}
+
+pub fn generator(input: &[u8]) -> Type {
+    parse(from_utf8(input).unwrap()).unwrap()
 }
 
 pub fn part1(input: &Type) -> usize {
@@ -91,81 +204,55 @@ pub fn part1(input: &Type) -> usize {
     l.len() / 2
 }
 
-fn compute_loop(position_start: &(u8, u8), map: &Map) -> ArrayVec<(u8, u8), 15000> {
-    let mut l = ArrayVec::new();
-    let mut position = position_start.to_owned();
-    let mut origin = Origin::North;
+/// Positions of the loop going through the start, ending with the start
+fn walk_loop(position_start: &(usize, usize), map: &Map) -> Result<Vec<(usize, usize)>, LoopError> {
+    let start = *position_start;
+    let mut l = vec![];
+    let mut position = start;
+    let mut exit = map[start.0][start.1].connections().ok_or(LoopError::Open)?[0];
     loop {
-        match (map[position.0 as usize][position.1 as usize], origin) {
-            (Pipe::Vertical, Origin::North) => position.0 += 1,
-            (Pipe::Vertical, Origin::South) => position.0 -= 1,
-            (Pipe::Horizontal, Origin::East) => position.1 -= 1,
-            (Pipe::Horizontal, Origin::West) => position.1 += 1,
-            (Pipe::NorthEast, Origin::North) => {
-                position.1 += 1;
-                origin = Origin::West
-            }
-            (Pipe::NorthEast, Origin::East) => {
-                position.0 -= 1;
-                origin = Origin::South
-            }
-            (Pipe::NorthWest, Origin::North) => {
-                position.1 -= 1;
-                origin = Origin::East;
-            }
-            (Pipe::NorthWest, Origin::West) => {
-                position.0 -= 1;
-                origin = Origin::South
-            }
-            (Pipe::SouthWest, Origin::South) => {
-                position.1 -= 1;
-                origin = Origin::East
-            }
-            (Pipe::SouthWest, Origin::West) => {
-                position.0 += 1;
-                origin = Origin::North
-            }
-            (Pipe::SouthEast, Origin::South) => {
-                position.1 += 1;
-                origin = Origin::West
-            }
-            (Pipe::SouthEast, Origin::East) => {
-                position.0 += 1;
-                origin = Origin::North
-            }
-            _ => unreachable!(),
-        }
-        l.push(position.to_owned());
-
-        if position == *position_start {
+        position = exit.step(position, map).ok_or(LoopError::Open)?;
+        l.push(position);
+        let entry = exit.opposite();
+        let connections = map[position.0][position.1]
+            .connections()
+            .filter(|c| c.contains(&entry))
+            .ok_or(LoopError::Open)?;
+        if position == start {
             break;
         }
+        exit = if connections[0] == entry {
+            connections[1]
+        } else {
+            connections[0]
+        };
     }
-    l
+    Ok(l)
 }
 
-pub fn part2(input: &Type) -> usize {
-    let (position_start, map) = input;
-    let loop_pipe = compute_loop(position_start, map)
-        .into_iter()
-        .collect::<HashSet<_>>();
+fn compute_loop(position_start: &(usize, usize), map: &Map) -> Vec<(usize, usize)> {
+    walk_loop(position_start, map).unwrap()
+}
 
+/// Tiles enclosed by the loop, in reading order. Only `render_loop` needs the tiles themselves,
+/// `part2` counts them with Pick's theorem.
+fn enclosed_tiles(map: &Map, loop_pipe: &HashSet<(usize, usize)>) -> Vec<(usize, usize)> {
     // create a new map with only the interesting pipe
     let mut new_map = map.to_owned();
     for (i, l) in map.iter().enumerate() {
         for (j, _) in l.iter().enumerate() {
-            if !loop_pipe.contains(&(i as u8, j as u8)) {
+            if !loop_pipe.contains(&(i, j)) {
                 new_map[i][j] = Pipe::None
             }
         }
     }
 
-    let mut n_inner = 0;
+    let mut enclosed = vec![];
     let mut is_inner = false;
     let mut from_north = false;
     let mut from_south = false;
-    for l in new_map.iter() {
-        for e in l.iter() {
+    for (i, l) in new_map.iter().enumerate() {
+        for (j, e) in l.iter().enumerate() {
             match e {
                 Pipe::Vertical => is_inner = !is_inner,
                 Pipe::Horizontal => {}
@@ -187,14 +274,50 @@ pub fn part2(input: &Type) -> usize {
                 Pipe::SouthEast => from_south = true,
                 Pipe::None => {
                     if is_inner {
-                        n_inner += 1;
+                        enclosed.push((i, j));
                     }
                 }
             }
         }
     }
 
-    n_inner
+    enclosed
+}
+
+pub fn part2(input: &Type) -> usize {
+    let (position_start, map) = input;
+    // the tiles of the loop are the vertices of a polygon with one boundary point per tile
+    let loop_pipe = compute_loop(position_start, map)
+        .into_iter()
+        .map(|(i, j)| (i as u64, j as u64))
+        .collect::<Vec<_>>();
+    interior_points(&loop_pipe).unwrap() as usize
+}
+
+/// Draw the main loop with box characters, the enclosed tiles as `I` and the other tiles as
+/// `O`. Also return the enclosed tiles, in reading order.
+pub fn render_loop(input: &Type) -> (String, Vec<(usize, usize)>) {
+    let (position_start, map) = input;
+    let loop_pipe = compute_loop(position_start, map)
+        .into_iter()
+        .collect::<HashSet<_>>();
+    let enclosed = enclosed_tiles(map, &loop_pipe);
+    let inside = enclosed.iter().collect::<HashSet<_>>();
+
+    let mut out = String::new();
+    for (i, l) in map.iter().enumerate() {
+        for (j, pipe) in l.iter().enumerate() {
+            if loop_pipe.contains(&(i, j)) {
+                write!(out, "{pipe}").unwrap();
+            } else if inside.contains(&(i, j)) {
+                out.push('I');
+            } else {
+                out.push('O');
+            }
+        }
+        out.push('\n');
+    }
+    (out, enclosed)
 }
 
 #[cfg(test)]
@@ -225,5 +348,81 @@ mod tests {
                             .........."
             .as_bytes();
         assert_eq!(part2(&generator(example)), 4);
+        assert_eq!(render_loop(&generator(example)).1.len(), 4);
+
+        let input = generator(include_bytes!("../inputs/day_10.txt"));
+        assert_eq!(render_loop(&input).1.len(), part2(&input));
+    }
+
+    #[test]
+    fn test_render() {
+        let example = "FF7FSF7F7F7F7F7F---7\n\
+            L|LJ||||||||||||F--J\n\
+            FL-7LJLJ||||||LJL-77\n\
+            F--JF--7||LJLJ7F7FJ-\n\
+            L---JF-JLJ.||-FJLJJ7\n\
+            |F|F-JF---7F7-L7L|7|\n\
+            |FFJF7L7F-JF7|JL---7\n\
+            7-L-JL7||F7|L7F-7F7|\n\
+            L.L7LFJ|||||FJL7||LJ\n\
+            L7JLJL-JLJLJL--JLJ.L";
+        let (text, enclosed) = render_loop(&parse(example).unwrap());
+        assert_eq!(enclosed.len(), 10);
+        assert_eq!(enclosed[0], (3, 14));
+        let expected = "O┌┐┌┐┌┐┌┐┌┐┌┐┌┐┌───┐\n\
+            O│└┘││││││││││││┌──┘\n\
+            O└─┐└┘└┘││││││└┘└─┐O\n\
+            ┌──┘┌──┐││└┘└┘I┌┐┌┘O\n\
+            └───┘┌─┘└┘IIII┌┘└┘OO\n\
+            OOO┌─┘┌───┐III└┐OOOO\n\
+            OO┌┘┌┐└┐┌─┘┌┐II└───┐\n\
+            OO└─┘└┐││┌┐│└┐┌─┐┌┐│\n\
+            OOOOO┌┘│││││┌┘└┐││└┘\n\
+            OOOOO└─┘└┘└┘└──┘└┘OO\n";
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn test_start() {
+        // S is a corner
+        let example = "7-F7-\n\
+            .FJ|7\n\
+            SJLL7\n\
+            |F--J\n\
+            LJ.LJ";
+        let (start, map) = parse(example).unwrap();
+        assert_eq!(map[start.0][start.1], Pipe::SouthEast);
+        assert_eq!(part1(&(start, map)), 8);
+
+        // a horizontal S on the first line, in a map larger than 140 tiles
+        let mut example = format!("F{}S{}7\n", "-".repeat(100), "-".repeat(100));
+        for _ in 0..200 {
+            example += &format!("|{}|\n", ".".repeat(201));
+        }
+        example += &format!("L{}J", "-".repeat(201));
+        let input = parse(&example).unwrap();
+        assert_eq!(input.1[0][101], Pipe::Horizontal);
+        assert_eq!(part1(&input), 203 + 200);
+        assert_eq!(part2(&input), 201 * 200);
+        assert_eq!(render_loop(&input).1.len(), 201 * 200);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(parse(".F7\n.LJ"), Err(LoopError::NoStart));
+        // S only connects to one pipe
+        assert_eq!(parse("S7\n.|\n.J"), Err(LoopError::Open));
+        // the loop is broken
+        assert_eq!(parse("S-7\n|.|\nL-."), Err(LoopError::Open));
+        // S has 3 connected neighbours, only its left and bottom ones close a loop
+        let (start, map) = parse("F-S-7\n|.|.|\nL-J.|\n...LJ").unwrap();
+        assert_eq!(map[start.0][start.1], Pipe::SouthWest);
+        // S can close a loop on its top right or on its bottom left
+        let err = parse(".....\n..F7.\n.FSJ.\n.LJ..\n.....").unwrap_err();
+        assert_eq!(
+            err,
+            LoopError::Ambiguous(vec![Pipe::NorthEast, Pipe::SouthWest])
+        );
+        assert_eq!(err.to_string(), "several pipes at S close a loop: └ ┐");
     }
 }
@@ -1,9 +1,7 @@
 use std::str::from_utf8;
 
-use arrayvec::ArrayVec;
-
-type Int = u32;
-type Input = ArrayVec<(Int, Int), 443>;
+type Int = u64;
+type Input = Vec<(Int, Int)>;
 
 pub fn generator(input: &[u8]) -> Input {
     let input = from_utf8(input).unwrap();
@@ -20,52 +18,87 @@ pub fn generator(input: &[u8]) -> Input {
         .collect()
 }
 
-fn dilate(mut positions: Input, f: Int) -> Input {
-    //dilate y
-    let mut d = 0;
-    let mut last_y = 0;
-    for i in 0..positions.len() {
-        if positions[i].0 - last_y > 1 {
-            d += (positions[i].0 - last_y - 1) * f;
+/// New coordinate of each of the sorted `values`, when every missing value in between is
+/// replaced by `factor` values
+fn expand_axis(values: &[Int], factor: Int) -> Vec<Int> {
+    let mut empty = 0;
+    let mut out = Vec::with_capacity(values.len());
+    for (k, v) in values.iter().enumerate() {
+        if k > 0 {
+            empty += (v - values[k - 1]).saturating_sub(1);
+        } else {
+            empty += v;
         }
-        last_y = positions[i].0;
-        positions[i].0 += d;
+        out.push(v - empty + empty * factor);
     }
+    out
+}
+
+/// Galaxies after the expansion, numbered from 1 in reading order as in the puzzle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Universe {
+    galaxies: Vec<(Int, Int)>,
+}
 
-    // dilate x
-    d = 0;
-    let mut last_x = 0;
-    positions.sort_unstable_by(|a, b| a.1.cmp(&b.1));
-    for i in 0..positions.len() {
-        if positions[i].1 - last_x > 1 {
-            d += (positions[i].1 - last_x - 1) * f;
+impl Universe {
+    pub fn new(input: &Input, factor: Int) -> Self {
+        let expand = |coordinate: fn(&(Int, Int)) -> Int| {
+            let mut values = input.iter().map(coordinate).collect::<Vec<_>>();
+            values.sort_unstable();
+            values.dedup();
+            let expanded = expand_axis(&values, factor);
+            input
+                .iter()
+                .map(|g| expanded[values.binary_search(&coordinate(g)).unwrap()])
+                .collect::<Vec<_>>()
+        };
+        let lines = expand(|g| g.0);
+        let columns = expand(|g| g.1);
+        Self {
+            galaxies: lines.into_iter().zip(columns).collect(),
         }
-        last_x = positions[i].1;
-        positions[i].1 += d;
     }
-    positions
+
+    pub fn galaxy(&self, n: usize) -> Option<(Int, Int)> {
+        self.galaxies.get(n.checked_sub(1)?).copied()
+    }
+
+    /// Length of the shortest path between galaxies `a` and `b`
+    pub fn distance(&self, a: usize, b: usize) -> Option<Int> {
+        let (a, b) = (self.galaxy(a)?, self.galaxy(b)?);
+        Some(a.0.abs_diff(b.0) + a.1.abs_diff(b.1))
+    }
+
+    /// Sum of the distances between every pair of galaxies, in O(n log n)
+    pub fn sum_distances(&self) -> Int {
+        let axis = |coordinate: fn(&(Int, Int)) -> Int| {
+            let mut values = self.galaxies.iter().map(coordinate).collect::<Vec<_>>();
+            values.sort_unstable();
+            // each value is the larger one of its pairs with all the values before it
+            let mut prefix = 0u128;
+            let mut sum = 0u128;
+            for (k, v) in values.iter().enumerate() {
+                sum += *v as u128 * k as u128 - prefix;
+                prefix += *v as u128;
+            }
+            sum
+        };
+        (axis(|g| g.0) + axis(|g| g.1))
+            .try_into()
+            .expect("sum too large for u64")
+    }
+}
+
+pub fn sum_distances(input: &Input, factor: Int) -> Int {
+    Universe::new(input, factor).sum_distances()
 }
 
 pub fn part1(input: &Input) -> u64 {
-    let input = dilate(input.to_owned(), 1);
-    let mut s = 0;
-    for (i, a) in input.iter().enumerate() {
-        for b in input[i + 1..].iter() {
-            s += ((b.0).abs_diff(a.0) + b.1.abs_diff(a.1)) as u64;
-        }
-    }
-    s
+    sum_distances(input, 2)
 }
 
 pub fn part2(input: &Input) -> u64 {
-    let input = dilate(input.to_owned(), 999999);
-    let mut s = 0;
-    for (i, a) in input.iter().enumerate() {
-        for b in input[i + 1..].iter() {
-            s += ((b.0).abs_diff(a.0) + b.1.abs_diff(a.1)) as u64;
-        }
-    }
-    s
+    sum_distances(input, 1_000_000)
 }
 
 #[cfg(test)]
@@ -91,12 +124,49 @@ mod tests {
             .as_bytes();
         assert_eq!(part1(&generator(example)), 374);
 
-        let input = dilate(generator(example).to_owned(), 9);
-        let s: u32 = iproduct!(&input, &input)
-            .map(|(a, b)| ((b.0).abs_diff(a.0) + b.1.abs_diff(a.1)))
+        let universe = Universe::new(&generator(example), 10);
+        let s: u64 = iproduct!(1..=9, 1..=9)
+            .filter(|(a, b)| a < b)
+            .map(|(a, b)| universe.distance(a, b).unwrap())
             .sum();
-        let s = s >> 1;
 
         assert_eq!(s, 1030);
+        assert_eq!(universe.sum_distances(), 1030);
+        assert_eq!(sum_distances(&generator(example), 10), 1030);
+        assert_eq!(sum_distances(&generator(example), 100), 8410);
+    }
+
+    #[test]
+    fn test_universe() {
+        let example = "...#......\n\
+                            .......#..\n\
+                            #.........\n\
+                            ..........\n\
+                            ......#...\n\
+                            .#........\n\
+                            .........#\n\
+                            ..........\n\
+                            .......#..\n\
+                            #...#....."
+            .as_bytes();
+        let universe = Universe::new(&generator(example), 2);
+        assert_eq!(universe.distance(5, 9), Some(9));
+        assert_eq!(universe.distance(1, 7), Some(15));
+        assert_eq!(universe.distance(3, 6), Some(17));
+        assert_eq!(universe.distance(8, 9), Some(5));
+        assert_eq!(universe.distance(8, 10), None);
+        assert_eq!(universe.distance(0, 1), None);
+
+        // empty rows and columns before the first galaxy expand too
+        let input = generator(b"..\n.#");
+        assert_eq!(Universe::new(&input, 3).galaxy(1), Some((3, 3)));
+        // a factor of 1 doesn't change anything and 0 removes the empty lines
+        let input = generator(example);
+        assert_eq!(Universe::new(&input, 1).galaxies, input);
+        assert_eq!(Universe::new(&input, 0).galaxy(9), Some((7, 3)));
+
+        // 292 without the empty lines, which are crossed 82 times by the shortest paths
+        let huge = sum_distances(&input, 1 << 40);
+        assert_eq!(huge, 292 + 82 * ((1 << 40) - 1));
     }
 }
@@ -2,7 +2,14 @@
 
 use std::str::from_utf8;
 
-type Input = Vec<(u128, u128, Vec<u8>, u8)>;
+/// A row of springs as `.`, `#` and `?` and the sizes of its groups of damaged springs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub springs: Vec<u8>,
+    pub groups: Vec<usize>,
+}
+
+type Input = Vec<Row>;
 
 pub fn generator(input: &[u8]) -> Input {
     let input = from_utf8(input).unwrap();
@@ -11,59 +18,246 @@ pub fn generator(input: &[u8]) -> Input {
         .lines()
         .map(|l| {
             let (map, groups) = l.split_once(' ').unwrap();
-            let mut damaged = 0;
-            let mut unknown = 0;
-
-            for b in map.bytes() {
-                damaged <<= 1;
-                unknown <<= 1;
-                match b {
-                    b'.' => {}
-                    b'?' => unknown |= 1,
-                    b'#' => damaged |= 1,
-                    _ => unreachable!(),
-                }
+            assert!(
+                map.bytes().all(|b| matches!(b, b'.' | b'?' | b'#')),
+                "unknown spring in {map}"
+            );
+            Row {
+                springs: map.bytes().collect(),
+                groups: groups.split(',').map(|v| v.parse().unwrap()).collect(),
             }
-            let groups = groups.split(',').map(|v| v.parse().unwrap()).collect();
-            (damaged, unknown, groups, map.len() as u8)
         })
         .collect()
 }
 
+/// Repeat the springs `factor` times separated by a `?`, and the groups `factor` times
+pub fn unfold(row: &Row, factor: usize) -> Row {
+    let mut springs = Vec::with_capacity((row.springs.len() + 1) * factor);
+    for k in 0..factor {
+        if k > 0 {
+            springs.push(b'?');
+        }
+        springs.extend_from_slice(&row.springs);
+    }
+    Row {
+        springs,
+        groups: row.groups.repeat(factor),
+    }
+}
+
+/// Number of ways to fill the `?` of the row so that it matches its groups
+pub fn count_arrangements(row: &Row) -> u64 {
+    count_unfolded(row, 1)
+}
+
+/// Same as `count_arrangements(&unfold(row, factor))`. Uses the bitmask version when the
+/// unfolded row has less than 128 springs and groups up to 16, a byte DP otherwise.
+pub fn count_unfolded(row: &Row, factor: usize) -> u64 {
+    let len = ((row.springs.len() + 1) * factor).saturating_sub(1);
+    let fits = len < 128 && row.groups.iter().all(|g| *g <= 16);
+    // count_valid needs at least one group
+    if !fits || row.groups.is_empty() || factor == 0 {
+        let row = unfold(row, factor);
+        return count_dp(&row.springs, &row.groups);
+    }
+    // count_valid writes past its tables when the groups can't fit in the row
+    let needed = (row.groups.iter().sum::<usize>() + row.groups.len()) * factor - 1;
+    if needed > len {
+        return 0;
+    }
+
+    let mut damaged = 0;
+    let mut unknown = 0;
+    let springs = (0..factor).flat_map(|k| {
+        let separator = (k > 0).then_some(&b'?');
+        separator.into_iter().chain(row.springs.iter())
+    });
+    for b in springs {
+        damaged <<= 1;
+        unknown <<= 1;
+        match b {
+            b'?' => unknown |= 1,
+            b'#' => damaged |= 1,
+            _ => {}
+        }
+    }
+    let groups = (0..factor)
+        .flat_map(|_| row.groups.iter().rev().map(|g| *g as u8))
+        .collect::<Vec<_>>();
+    count_valid(damaged, unknown, &groups, len as u8)
+}
+
+/// Sum of the arrangements of all the rows, unfolded `factor` times
+pub fn sum_arrangements(input: &Input, factor: usize) -> u64 {
+    input.iter().map(|row| count_unfolded(row, factor)).sum()
+}
+
 pub fn part1(input: &Input) -> u64 {
-    input
-        .iter()
-        .map(|(damaged, unknown, groups, n_bits)| {
-            let mut groups = groups.to_owned();
-            groups.reverse();
-            let n_damaged = groups.len() as u8;
-            let _cache = vec![u64::MAX; *n_bits as usize * n_damaged as usize];
-            count_valid(*damaged, *unknown, &groups, *n_bits)
-        })
-        .sum()
+    sum_arrangements(input, 1)
 }
 
 pub fn part2(input: &Input) -> u64 {
-    input
-        .iter()
-        .map(|(damaged, unknown, count, n_bits)| {
-            let mut count = count.to_owned();
-            count.reverse();
-            let mut new_damaged = *damaged;
-            let mut new_unknown = *unknown;
-            for _ in 0..4 {
-                new_unknown <<= 1;
-                new_unknown += 1;
-                new_unknown <<= n_bits;
-                new_unknown += unknown;
-                new_damaged <<= 1 + n_bits;
-                new_damaged += damaged;
+    sum_arrangements(input, 5)
+}
+
+/// Number of ways to place `groups[g..]` in `springs[i..]` for every `g` and `i`, used to list
+/// or sample the arrangements of a row
+#[derive(Debug, Clone)]
+pub struct ArrangementTable {
+    springs: Vec<u8>,
+    groups: Vec<usize>,
+    // (groups.len() + 1) lines of springs.len() + 2 positions
+    ways: Vec<u64>,
+}
+
+impl ArrangementTable {
+    pub fn new(row: &Row) -> Self {
+        let n = row.springs.len();
+        let n_groups = row.groups.len();
+        let mut table = Self {
+            springs: row.springs.clone(),
+            groups: row.groups.clone(),
+            ways: vec![0; (n_groups + 1) * (n + 2)],
+        };
+        // run[i] is the number of springs that can be damaged from i
+        let mut run = vec![0; n + 1];
+        for i in (0..n).rev() {
+            run[i] = if row.springs[i] == b'.' {
+                0
+            } else {
+                run[i + 1] + 1
+            };
+        }
+        for g in (0..=n_groups).rev() {
+            for i in (0..=n + 1).rev() {
+                let ways = if i >= n {
+                    (g == n_groups) as u64
+                } else {
+                    let operational = if row.springs[i] != b'#' {
+                        table.get(g, i + 1)
+                    } else {
+                        0
+                    };
+                    let damaged = match row.groups.get(g) {
+                        Some(size)
+                            if run[i] >= *size && row.springs.get(i + size) != Some(&b'#') =>
+                        {
+                            table.get(g + 1, i + size + 1)
+                        }
+                        _ => 0,
+                    };
+                    operational
+                        .checked_add(damaged)
+                        .expect("too many arrangements for u64")
+                };
+                table.ways[g * (n + 2) + i] = ways;
             }
-            let new_count = count.repeat(5);
+        }
+        table
+    }
 
-            count_valid(new_damaged, new_unknown, &new_count, n_bits * 5 + 4)
-        })
-        .sum()
+    fn get(&self, g: usize, i: usize) -> u64 {
+        let n = self.springs.len();
+        self.ways[g * (n + 2) + i.min(n + 1)]
+    }
+
+    pub fn count(&self) -> u64 {
+        self.get(0, 0)
+    }
+
+    /// The arrangement of index `k`, when the arrangements are ordered with the operational
+    /// springs first
+    pub fn nth(&self, mut k: u64) -> Option<String> {
+        if k >= self.count() {
+            return None;
+        }
+        let n = self.springs.len();
+        let mut out = String::with_capacity(n);
+        let (mut g, mut i) = (0, 0);
+        while i < n {
+            let operational = if self.springs[i] != b'#' {
+                self.get(g, i + 1)
+            } else {
+                0
+            };
+            if k < operational {
+                out.push('.');
+                i += 1;
+            } else {
+                k -= operational;
+                let size = self.groups[g];
+                out.extend(std::iter::repeat_n('#', size));
+                if i + size < n {
+                    out.push('.');
+                }
+                g += 1;
+                i += size + 1;
+            }
+        }
+        Some(out)
+    }
+
+    /// The first `limit` arrangements as `#` and `.`, in the order of `nth`
+    pub fn iter(&self, limit: usize) -> impl Iterator<Item = String> + '_ {
+        (0..self.count().min(limit as u64)).map(|k| self.nth(k).unwrap())
+    }
+
+    /// One of the arrangements chosen uniformly, `random_below(n)` must return a uniform random
+    /// number below `n`
+    pub fn sample(&self, mut random_below: impl FnMut(u64) -> u64) -> Option<String> {
+        match self.count() {
+            0 => None,
+            count => self.nth(random_below(count)),
+        }
+    }
+}
+
+/// Same as `count_valid` for any length: `ways[i]` is the number of ways to place the groups
+/// seen so far in the first `i` springs
+fn count_dp(springs: &[u8], groups: &[usize]) -> u64 {
+    let n = springs.len();
+    // number of springs that can be damaged just before each position
+    let mut run = vec![0; n + 1];
+    for i in 0..n {
+        run[i + 1] = if springs[i] == b'.' { 0 } else { run[i] + 1 };
+    }
+
+    // no group yet: valid as long as no spring is known to be damaged
+    let mut previous = vec![0u64; n + 1];
+    previous[0] = 1;
+    for i in 0..n {
+        if springs[i] == b'#' {
+            break;
+        }
+        previous[i + 1] = 1;
+    }
+
+    let mut current = vec![0u64; n + 1];
+    for (g, size) in groups.iter().enumerate() {
+        current[0] = 0;
+        for i in 1..=n {
+            // spring i - 1 is operational
+            let mut ways = if springs[i - 1] != b'#' {
+                current[i - 1]
+            } else {
+                0
+            };
+            // the group ends at spring i - 1, after an operational one if it's not the first
+            if run[i] >= *size {
+                let start = i - size;
+                if start == 0 {
+                    ways += (g == 0) as u64;
+                } else if springs[start - 1] != b'#' {
+                    ways = ways
+                        .checked_add(previous[start - 1])
+                        .expect("too many arrangements for u64");
+                }
+            }
+            current[i] = ways;
+        }
+        (previous, current) = (current, previous);
+    }
+    previous[n]
 }
 
 fn count_valid(damaged: u128, unknown: u128, count: &[u8], max_n_bits: u8) -> u64 {
@@ -166,4 +360,137 @@ mod tests {
         assert_eq!(part1(&generator(example)), 21);
         assert_eq!(part2(&generator(example)), 525152);
     }
+
+    fn brute_force(row: &Row) -> Vec<String> {
+        let unknown = row.springs.iter().filter(|b| **b == b'?').count();
+        let mut out = vec![];
+        for mask in 0..1u32 << unknown {
+            let mut k = 0;
+            let springs = row
+                .springs
+                .iter()
+                .map(|b| match b {
+                    b'?' => {
+                        k += 1;
+                        if mask >> (k - 1) & 1 == 1 {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    }
+                    b => *b as char,
+                })
+                .collect::<String>();
+            let groups = springs
+                .split('.')
+                .filter(|g| !g.is_empty())
+                .map(|g| g.len())
+                .collect::<Vec<_>>();
+            if groups == row.groups {
+                out.push(springs);
+            }
+        }
+        out.sort();
+        out
+    }
+
+    #[test]
+    fn test_arrangements() {
+        let input = generator(include_bytes!("../inputs/day_12.txt"));
+        for row in input.iter().take(100) {
+            let table = ArrangementTable::new(row);
+            assert_eq!(table.count(), count_arrangements(row));
+            let mut listed = table.iter(usize::MAX).collect::<Vec<_>>();
+            listed.sort();
+            assert_eq!(listed, brute_force(row));
+        }
+
+        let example = generator(b"?###???????? 3,2,1");
+        let table = ArrangementTable::new(&example[0]);
+        let listed = table.iter(3).collect::<Vec<_>>();
+        assert_eq!(listed, vec![".###....##.#", ".###...##..#", ".###...##.#."]);
+        assert_eq!(table.iter(100).count(), 10);
+
+        let table = ArrangementTable::new(&unfold(&generator(b"???.### 1,1,3")[0], 2));
+        assert_eq!(table.count(), 1);
+        assert_eq!(table.nth(0).unwrap(), "#.#.###.#.#.###");
+        assert_eq!(table.nth(1), None);
+        assert_eq!(
+            ArrangementTable::new(&generator(b"#.# 1")[0]).sample(|_| 0),
+            None
+        );
+    }
+
+    #[test]
+    fn test_sample() {
+        let table = ArrangementTable::new(&generator(b"?###???????? 3,2,1")[0]);
+        // deterministic linear congruential generator
+        let mut state = 42u64;
+        let mut random_below = |n: u64| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) % n
+        };
+        let mut counts = ahash::HashMap::default();
+        for _ in 0..10_000 {
+            *counts
+                .entry(table.sample(&mut random_below).unwrap())
+                .or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), 10);
+        assert!(
+            counts.values().all(|c| (850..1150).contains(c)),
+            "{counts:?}"
+        );
+    }
+
+    #[test]
+    fn test_long_rows() {
+        let row = |springs: &str, groups: &[usize]| Row {
+            springs: springs.bytes().collect(),
+            groups: groups.to_vec(),
+        };
+        // the byte DP gives the same counts as the bitmask one
+        let input = generator(include_bytes!("../inputs/day_12.txt"));
+        for r in input.iter().take(200) {
+            for factor in [1, 2, 5] {
+                let unfolded = unfold(r, factor);
+                assert_eq!(
+                    count_dp(&unfolded.springs, &unfolded.groups),
+                    count_unfolded(r, factor)
+                );
+            }
+        }
+
+        // groups longer than 16
+        assert_eq!(count_arrangements(&row(&"?".repeat(25), &[20])), 6);
+        assert_eq!(count_arrangements(&row(&"#".repeat(17), &[17])), 1);
+        assert_eq!(count_arrangements(&row(&"#".repeat(17), &[16])), 0);
+        // rows longer than 127
+        let long = "?".repeat(200);
+        assert_eq!(count_arrangements(&row(&long, &[1])), 200);
+        assert_eq!(count_arrangements(&row(&long, &[1, 1])), 199 * 198 / 2);
+        assert_eq!(count_arrangements(&row(&long, &[200])), 1);
+        assert_eq!(count_arrangements(&row(&long, &[201])), 0);
+
+        let example = generator(b"?###???????? 3,2,1");
+        assert_eq!(sum_arrangements(&example, 5), 506250);
+        assert_eq!(unfold(&example[0], 12).springs.len(), 12 * 13 - 1);
+        assert_eq!(sum_arrangements(&example, 0), 1);
+        assert_eq!(count_arrangements(&row("..?", &[])), 1);
+        assert_eq!(count_arrangements(&row("..#", &[])), 0);
+
+        // groups that can't fit in the row
+        for (springs, groups) in [("?", &[5][..]), ("?.?", &[2, 2]), ("???", &[1, 2])] {
+            let row = row(springs, groups);
+            assert_eq!(count_arrangements(&row), 0);
+            assert_eq!(count_unfolded(&row, 5), 0);
+        }
+        assert_eq!(sum_arrangements(&generator(b"? 5\n?.? 2,2\n"), 1), 0);
+        assert_eq!(
+            count_unfolded(&row("?.?", &[1, 1]), 2),
+            count_dp(b"?.???.?", &[1; 4])
+        );
+    }
 }
@@ -1,84 +1,127 @@
 use std::str::from_utf8;
 
-use arrayvec::ArrayVec;
+/// Cells of a row or a column, `#` is a set bit
+type Bits = Vec<u64>;
 
-type Input<'a> = ArrayVec<ArrayVec<&'a [u8], 17>, 100>;
+/// A pattern stored both by rows and by columns so both axes are compared the same way
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    rows: Vec<Bits>,
+    columns: Vec<Bits>,
+}
+
+impl Pattern {
+    pub fn new(lines: &[&[u8]]) -> Self {
+        let height = lines.len();
+        let width = lines.first().map_or(0, |l| l.len());
+        let mut rows = vec![vec![0; width.div_ceil(64)]; height];
+        let mut columns = vec![vec![0; height.div_ceil(64)]; width];
+        for (i, line) in lines.iter().enumerate() {
+            assert_eq!(line.len(), width, "pattern rows have different lengths");
+            for (j, cell) in line.iter().enumerate() {
+                match cell {
+                    b'#' => {
+                        rows[i][j / 64] |= 1 << (j % 64);
+                        columns[j][i / 64] |= 1 << (i % 64);
+                    }
+                    b'.' => {}
+                    _ => panic!("unexpected cell {:?}", *cell as char),
+                }
+            }
+        }
+        Self { rows, columns }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    /// Mirror below the given number of rows
+    Horizontal(usize),
+    /// Mirror right of the given number of columns
+    Vertical(usize),
+}
+
+impl Axis {
+    pub fn summary(&self) -> usize {
+        match self {
+            Axis::Horizontal(rows) => rows * 100,
+            Axis::Vertical(columns) => *columns,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reflection {
+    pub axis: Axis,
+    /// Number of cells differing from their mirror image
+    pub mismatches: usize,
+}
+
+/// Number of differing cells when mirroring `lines` after `n` of them, None once it goes above
+/// `smudges`
+fn mismatches(lines: &[Bits], n: usize, smudges: usize) -> Option<usize> {
+    let mut count = 0;
+    for (a, b) in lines[..n].iter().rev().zip(&lines[n..]) {
+        count += a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| (a ^ b).count_ones() as usize)
+            .sum::<usize>();
+        if count > smudges {
+            return None;
+        }
+    }
+    Some(count)
+}
+
+/// Every axis whose reflection has at most `smudges` mismatching cells, the horizontal ones
+/// first
+pub fn find_reflections(pattern: &Pattern, smudges: usize) -> Vec<Reflection> {
+    let axes = |lines: &[Bits], axis: fn(usize) -> Axis| {
+        (1..lines.len())
+            .filter_map(|n| {
+                mismatches(lines, n, smudges).map(|mismatches| Reflection {
+                    axis: axis(n),
+                    mismatches,
+                })
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut reflections = axes(&pattern.rows, Axis::Horizontal);
+    reflections.extend(axes(&pattern.columns, Axis::Vertical));
+    reflections
+}
+
+type Input = Vec<Pattern>;
 
 pub fn generator(input: &[u8]) -> Input {
     let input = from_utf8(input).unwrap();
 
     let block = input.split("\n\n");
     block
-        .map(|b| b.lines().map(|line| line.as_bytes()).collect())
+        .map(|b| Pattern::new(&b.lines().map(|line| line.as_bytes()).collect::<Vec<_>>()))
         .collect()
 }
 
-pub fn part1(input: &Input) -> usize {
-    let mut s = 0;
-    'block: for block in input {
-        let block_h = block.len();
-        let block_w = block[0].len();
-        // test line reflection
-        for i in 0..block_h - 1 {
-            if (0..=i.min(block_h - i - 2))
-                .all(|j| (0..block_w).all(|k| block[i - j][k] == block[i + 1 + j][k]))
-            {
-                s += (i + 1) * 100;
-                continue 'block;
-            }
-        }
+/// Sum of the summaries of the first axis of each pattern with exactly `smudges` mismatches
+fn summarize(input: &Input, smudges: usize) -> usize {
+    input
+        .iter()
+        .map(|pattern| {
+            find_reflections(pattern, smudges)
+                .into_iter()
+                .find(|r| r.mismatches == smudges)
+                .map_or(0, |r| r.axis.summary())
+        })
+        .sum()
+}
 
-        // test column reflection
-        for i in 0..block_w - 1 {
-            if (0..=i.min(block_w - i - 2))
-                .all(|j| (0..block_h).all(|k| block[k][i - j] == block[k][i + 1 + j]))
-            {
-                s += i + 1;
-                continue 'block;
-            }
-        }
-    }
-    s
+pub fn part1(input: &Input) -> usize {
+    summarize(input, 0)
 }
 
 pub fn part2(input: &Input) -> usize {
-    let mut s = 0;
-    'block: for block in input {
-        let block_h = block.len();
-        let block_w = block[0].len();
-        // test line reflection
-        for i in 0..block_h - 1 {
-            if (0..=i.min(block_h - i - 2))
-                .map(|j| {
-                    (0..block_w)
-                        .map(|k| (block[i - j][k] != block[i + 1 + j][k]) as usize)
-                        .sum::<usize>()
-                })
-                .sum::<usize>()
-                == 1
-            {
-                s += (i + 1) * 100;
-                continue 'block;
-            }
-        }
-
-        // test column reflection
-        for i in 0..block_w - 1 {
-            if (0..=i.min(block_w - i - 2))
-                .map(|j| {
-                    (0..block_h)
-                        .map(|k| (block[k][i - j] != block[k][i + 1 + j]) as usize)
-                        .sum::<usize>()
-                })
-                .sum::<usize>()
-                == 1
-            {
-                s += i + 1;
-                continue 'block;
-            }
-        }
-    }
-    s
+    summarize(input, 1)
 }
 
 #[cfg(test)]
@@ -109,4 +152,62 @@ mod tests {
         assert_eq!(part1(&generator(example)), 405);
         assert_eq!(part2(&generator(example)), 400);
     }
+
+    #[test]
+    fn test_find_reflections() {
+        let pattern = generator(
+            b"#.##..##.\n..#.##.#.\n##......#\n##......#\n..#.##.#.\n..##..##.\n#.#.##.#.",
+        );
+        assert_eq!(
+            find_reflections(&pattern[0], 0),
+            vec![Reflection {
+                axis: Axis::Vertical(5),
+                mismatches: 0
+            }]
+        );
+        let reflections = find_reflections(&pattern[0], 1);
+        assert!(reflections.contains(&Reflection {
+            axis: Axis::Horizontal(3),
+            mismatches: 1
+        }));
+        assert_eq!(reflections.len(), 2);
+        // with enough smudges every axis reflects
+        assert_eq!(find_reflections(&pattern[0], 63).len(), 6 + 8);
+
+        // wider and taller than a word
+        let wide = format!("{}\n{}", "#.".repeat(50), "#.".repeat(50));
+        let reflections = find_reflections(&generator(wide.as_bytes())[0], 0);
+        assert_eq!(
+            reflections,
+            vec![Reflection {
+                axis: Axis::Horizontal(1),
+                mismatches: 0
+            }]
+        );
+        // every row is different, the left half is the binary of its index
+        let mut tall = (0..150u8)
+            .map(|i| {
+                let half = format!("{i:08b}").replace('0', ".").replace('1', "#");
+                half.clone() + &half.chars().rev().collect::<String>()
+            })
+            .collect::<Vec<_>>();
+        let pattern = generator(tall.join("\n").as_bytes());
+        assert_eq!(
+            find_reflections(&pattern[0], 1),
+            vec![Reflection {
+                axis: Axis::Vertical(8),
+                mismatches: 0
+            }]
+        );
+        tall[100].replace_range(0..1, "#");
+        let pattern = generator(tall.join("\n").as_bytes());
+        assert_eq!(find_reflections(&pattern[0], 0), vec![]);
+        assert_eq!(
+            find_reflections(&pattern[0], 1),
+            vec![Reflection {
+                axis: Axis::Vertical(8),
+                mismatches: 1
+            }]
+        );
+    }
 }
@@ -1,128 +1,216 @@
-use std::str::from_utf8;
+use std::{fmt::Display, str::from_utf8};
 
 use ahash::{HashMap, HashMapExt};
 
-use arrayvec::ArrayVec;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tilt {
+    North,
+    West,
+    South,
+    East,
+}
 
-type Input = ArrayVec<ArrayVec<u8, 100>, 100>;
+pub const SPIN_CYCLE: [Tilt; 4] = [Tilt::North, Tilt::West, Tilt::South, Tilt::East];
 
-pub fn generator(input: &[u8]) -> Input {
-    let input = from_utf8(input).unwrap();
+/// Mask of the bits of `word` in the range `start..end` of a row
+fn word_mask(word: usize, start: usize, end: usize) -> u64 {
+    let lo = start.max(word * 64) - word * 64;
+    let hi = end.min(word * 64 + 64) - word * 64;
+    if hi - lo == 64 {
+        !0
+    } else {
+        ((1 << (hi - lo)) - 1) << lo
+    }
+}
 
-    input.lines().map(|line| line.bytes().collect()).collect()
+/// Rows of rounded and cube rocks packed in bits, each row starting on a new word
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Platform {
+    width: usize,
+    height: usize,
+    words: usize,
+    rounded: Vec<u64>,
+    cubes: Vec<u64>,
+    /// Row, start and end of the runs without cube rocks, where rounded rocks roll sideways
+    segments: Vec<(usize, usize, usize)>,
 }
 
-pub fn part1(input: &Input) -> usize {
-    let h = input.len();
-    let w = input[0].len();
-    let mut input = input.to_owned();
-
-    for c in 0..w {
-        let mut next_position = 0;
-        for l in 0..h {
-            if input[l][c] == b'O' {
-                input[l][c] = b'.';
-                input[next_position][c] = b'O';
-                next_position += 1;
-            } else if input[l][c] == b'#' {
-                next_position = l + 1;
+impl Platform {
+    pub fn new(input: &[u8]) -> Self {
+        let input = from_utf8(input).unwrap();
+        let lines = input.lines().collect::<Vec<_>>();
+        let height = lines.len();
+        let width = lines.first().map_or(0, |l| l.len());
+        let words = width.div_ceil(64);
+        let mut platform = Self {
+            width,
+            height,
+            words,
+            rounded: vec![0; height * words],
+            cubes: vec![0; height * words],
+            segments: vec![],
+        };
+        for (r, line) in lines.iter().enumerate() {
+            assert_eq!(line.len(), width, "platform rows have different lengths");
+            let mut start = 0;
+            for (c, b) in line.bytes().enumerate() {
+                let (word, bit) = (r * words + c / 64, 1 << (c % 64));
+                match b {
+                    b'O' => platform.rounded[word] |= bit,
+                    b'#' => {
+                        platform.cubes[word] |= bit;
+                        if start < c {
+                            platform.segments.push((r, start, c));
+                        }
+                        start = c + 1;
+                    }
+                    b'.' => {}
+                    _ => panic!("unexpected cell {:?}", b as char),
+                }
+            }
+            if start < width {
+                platform.segments.push((r, start, width));
             }
         }
+        platform
     }
 
-    total_load(input)
-}
-
-pub fn part2(input: &Input) -> usize {
-    let h = input.len();
-    let w = input[0].len();
-    let mut input = input.to_owned();
-
-    let mut map_to_step = HashMap::new();
-    for i in 0..30000 {
-        step(h, w, &mut input);
-        if map_to_step.contains_key(&input) {
-            let previous_i = map_to_step.get(&input).unwrap();
-            let period = (1000000000 - 1 - i) / (i - previous_i);
-            let i = i + period * (i - previous_i);
-            let r = 999999999 - i;
-            for _ in 0..r {
-                step(h, w, &mut input);
-            }
-            return total_load(input);
+    pub fn tilt(&mut self, tilt: Tilt) {
+        match tilt {
+            Tilt::North => self.tilt_vertical(true),
+            Tilt::South => self.tilt_vertical(false),
+            Tilt::West => self.tilt_horizontal(true),
+            Tilt::East => self.tilt_horizontal(false),
         }
-
-        map_to_step.insert(input.to_owned(), i);
     }
-    0
-}
 
-fn total_load(input: Input) -> usize {
-    input
-        .iter()
-        .rev()
-        .enumerate()
-        .map(|(u, l)| l.iter().filter(|e| **e == b'O').count() * (u + 1))
-        .sum()
-}
-
-fn step(h: usize, w: usize, input: &mut Input) {
-    let mut next_position;
-    for c in 0..w {
-        next_position = 0;
-        for l in 0..h {
-            if input[l][c] == b'O' {
-                input[l][c] = b'.';
-                input[next_position][c] = b'O';
-                next_position += 1;
-            } else if input[l][c] == b'#' {
-                next_position = l + 1;
+    /// Settle the rows from the side the rocks roll to, so each rock only moves through rows
+    /// that already hold their final content
+    fn tilt_vertical(&mut self, north: bool) {
+        let h = self.height;
+        for n in 1..h {
+            let r = if north { n } else { h - 1 - n };
+            for w in 0..self.words {
+                let mut k = r;
+                let mut moving = self.rounded[k * self.words + w];
+                self.rounded[k * self.words + w] = 0;
+                loop {
+                    let next = if north {
+                        k.checked_sub(1)
+                    } else {
+                        Some(k + 1).filter(|k| *k < h)
+                    };
+                    let rolling = next.map_or(0, |next| {
+                        let i = next * self.words + w;
+                        moving & !(self.rounded[i] | self.cubes[i])
+                    });
+                    self.rounded[k * self.words + w] |= moving & !rolling;
+                    moving = rolling;
+                    match next {
+                        Some(next) if moving != 0 => k = next,
+                        _ => break,
+                    }
+                }
             }
         }
     }
 
-    for l in 0..h {
-        next_position = 0;
-        for c in 0..w {
-            if input[l][c] == b'O' {
-                input[l][c] = b'.';
-                input[l][next_position] = b'O';
-                next_position += 1;
-            } else if input[l][c] == b'#' {
-                next_position = c + 1;
+    /// Gather the rocks of each segment at the side they roll to
+    fn tilt_horizontal(&mut self, west: bool) {
+        for &(r, start, end) in self.segments.iter() {
+            let row = &mut self.rounded[r * self.words..(r + 1) * self.words];
+            let words = start / 64..=(end - 1) / 64;
+            let count = words
+                .clone()
+                .map(|w| (row[w] & word_mask(w, start, end)).count_ones() as usize)
+                .sum::<usize>();
+            let (from, to) = if west {
+                (start, start + count)
+            } else {
+                (end - count, end)
+            };
+            for w in words {
+                row[w] &= !word_mask(w, start, end);
+                if from < to && w * 64 < to && from < w * 64 + 64 {
+                    row[w] |= word_mask(w, from, to);
+                }
             }
         }
     }
 
-    for c in 0..w {
-        next_position = h - 1;
-        for l in (0..h).rev() {
-            if input[l][c] == b'O' {
-                input[l][c] = b'.';
-                input[next_position][c] = b'O';
-                next_position = next_position.wrapping_sub(1);
-            } else if input[l][c] == b'#' {
-                next_position = l.wrapping_sub(1);
+    /// Apply `cycles` times the sequence of tilts, skipping ahead once the rocks come back to a
+    /// previous position
+    pub fn spin(&mut self, tilts: &[Tilt], cycles: u64) {
+        let mut seen = HashMap::new();
+        let mut history = vec![];
+        for i in 0..cycles {
+            if let Some(&previous) = seen.get(&self.rounded) {
+                let period = i - previous;
+                let offset = previous + (cycles - i) % period;
+                self.rounded = history.swap_remove(offset as usize);
+                return;
+            }
+            seen.insert(self.rounded.clone(), i);
+            history.push(self.rounded.clone());
+            for tilt in tilts {
+                self.tilt(*tilt);
             }
         }
     }
 
-    for l in 0..h {
-        next_position = h - 1;
-        for c in (0..w).rev() {
-            if input[l][c] == b'O' {
-                input[l][c] = b'.';
-                input[l][next_position] = b'O';
-                next_position = next_position.wrapping_sub(1);
-            } else if input[l][c] == b'#' {
-                next_position = c.wrapping_sub(1);
+    /// Load on the north support beams
+    pub fn load(&self) -> usize {
+        (0..self.height)
+            .map(|r| {
+                let row = &self.rounded[r * self.words..(r + 1) * self.words];
+                row.iter().map(|w| w.count_ones() as usize).sum::<usize>() * (self.height - r)
+            })
+            .sum()
+    }
+}
+
+impl Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for r in 0..self.height {
+            for c in 0..self.width {
+                let (word, bit) = (r * self.words + c / 64, 1 << (c % 64));
+                let cell = if self.rounded[word] & bit != 0 {
+                    'O'
+                } else if self.cubes[word] & bit != 0 {
+                    '#'
+                } else {
+                    '.'
+                };
+                write!(f, "{cell}")?;
             }
+            writeln!(f)?;
         }
+        Ok(())
     }
 }
 
+type Input = Platform;
+
+pub fn generator(input: &[u8]) -> Input {
+    Platform::new(input)
+}
+
+pub fn part1(input: &Input) -> usize {
+    let mut platform = input.clone();
+    platform.tilt(Tilt::North);
+    platform.load()
+}
+
+pub fn part2(input: &Input) -> usize {
+    let mut platform = input.clone();
+    platform.spin(&SPIN_CYCLE, 1_000_000_000);
+    platform.load()
+}
+
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
+
     use super::*;
 
     use aoc_macro::test_parts;
@@ -144,4 +232,102 @@ mod tests {
         assert_eq!(part1(&generator(example)), 136);
         assert_eq!(part2(&generator(example)), 64);
     }
+
+    /// Tilt by rolling the rocks of each line one by one, cell coordinates listed from the side
+    /// they roll to
+    fn naive_tilt(grid: &mut [Vec<u8>], tilt: Tilt) {
+        let (h, w) = (grid.len(), grid[0].len());
+        let lines: Vec<Vec<_>> = match tilt {
+            Tilt::North => (0..w).map(|c| (0..h).map(|r| (r, c)).collect()).collect(),
+            Tilt::South => (0..w)
+                .map(|c| (0..h).rev().map(|r| (r, c)).collect())
+                .collect(),
+            Tilt::West => (0..h).map(|r| (0..w).map(|c| (r, c)).collect()).collect(),
+            Tilt::East => (0..h)
+                .map(|r| (0..w).rev().map(|c| (r, c)).collect())
+                .collect(),
+        };
+        for line in lines {
+            let mut free = 0;
+            for (k, &(r, c)) in line.iter().enumerate() {
+                match grid[r][c] {
+                    b'#' => free = k + 1,
+                    b'O' => {
+                        grid[r][c] = b'.';
+                        let (r, c) = line[free];
+                        grid[r][c] = b'O';
+                        free += 1;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_spin() {
+        let example = "O....#....\nO.OO#....#\n.....##...\nOO.#O....O\n.O.....O#.\n\
+                       O.#..O.#.#\n..O..#O..O\n.......O..\n#....###..\n#OO..#....\n";
+        let mut platform = generator(example.as_bytes());
+        platform.spin(&SPIN_CYCLE, 0);
+        assert_eq!(platform.to_string(), example);
+        platform.spin(&SPIN_CYCLE, 1);
+        assert_eq!(
+            platform.to_string(),
+            ".....#....\n....#...O#\n...OO##...\n.OO#......\n.....OOO#.\n\
+             .O#...O#.#\n....O#....\n......OOOO\n#...O###..\n#..OO#....\n"
+        );
+        platform.spin(&SPIN_CYCLE, 2);
+        assert_eq!(
+            platform.to_string(),
+            ".....#....\n....#...O#\n.....##...\n..O#......\n.....OOO#.\n\
+             .O#...O#.#\n....O#...O\n.......OOO\n#...O###.O\n#.OOO#...O\n"
+        );
+
+        // the same rocks after any large number of cycles, as long as it's the same modulo
+        // the period
+        let load = |cycles| {
+            let mut platform = generator(example.as_bytes());
+            platform.spin(&SPIN_CYCLE, cycles);
+            platform.load()
+        };
+        assert_eq!(
+            load(1_000_000_000),
+            load(u64::MAX - (u64::MAX - 1_000_000_000) % 7)
+        );
+        platform.spin(&[], u64::MAX);
+        assert_eq!(platform.load(), load(3));
+    }
+
+    fn to_text(grid: &[Vec<u8>]) -> String {
+        grid.iter()
+            .map(|l| String::from_utf8(l.clone()).unwrap() + "\n")
+            .collect()
+    }
+
+    // grids up to two words wide and tall, with a sequence of tilts
+    fn grids() -> impl Strategy<Value = (Vec<Vec<u8>>, Vec<Tilt>)> {
+        (1..=130usize, 1..=130usize).prop_flat_map(|(h, w)| {
+            (
+                prop::collection::vec(
+                    prop::collection::vec(prop::sample::select(b"O#..".to_vec()), w),
+                    h,
+                ),
+                prop::collection::vec(prop::sample::select(SPIN_CYCLE.to_vec()), 0..8),
+            )
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_any_size((mut grid, tilts) in grids()) {
+            let mut platform = generator(to_text(&grid).as_bytes());
+            prop_assert_eq!(platform.to_string(), to_text(&grid));
+            for tilt in tilts {
+                platform.tilt(tilt);
+                naive_tilt(&mut grid, tilt);
+                prop_assert_eq!(platform.to_string(), to_text(&grid), "{:?}", tilt);
+            }
+        }
+    }
 }
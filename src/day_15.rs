@@ -1,8 +1,10 @@
-use std::str::from_utf8;
+use std::{collections::BTreeMap, fmt::Display, str::from_utf8};
+
+use ahash::{HashMap, HashMapExt};
 
 type Type<'a> = Vec<&'a str>;
 
-pub fn generator(input: &[u8]) -> Type {
+pub fn generator(input: &[u8]) -> Type<'_> {
     let input = from_utf8(input).unwrap();
 
     input.lines().next().unwrap().split(',').collect()
@@ -21,52 +23,199 @@ pub fn part1(input: &Type) -> u32 {
     input.iter().map(|w| compute_hash(w) as u32).sum()
 }
 
-#[derive(Clone, Copy, Debug)]
-struct Element<'a> {
-    label: &'a str,
-    value: u16,
+/// A malformed step of the initialization sequence, `index` is its position in the sequence
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepError {
+    MissingOperation { index: usize, step: String },
+    InvalidLabel { index: usize, step: String },
+    InvalidFocalLength { index: usize, step: String },
 }
 
-pub fn part2(input: &Type) -> u32 {
-    let mut hashmap: Vec<Vec<Element>> = vec![vec![]; 256];
-    'outer: for w in input {
-        if let Some((label, value)) = w.split_once('=') {
-            let hash = compute_hash(label);
-            let value = value.parse().unwrap();
-
-            let v = hashmap.get_mut(hash as usize).unwrap();
-            for element in v.iter_mut() {
-                if element.label == label {
-                    element.value = value;
-                    continue 'outer;
-                }
+impl Display for StepError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StepError::MissingOperation { index, step } => {
+                write!(f, "step {index} {step:?} has no '=' or '-' operation")
+            }
+            StepError::InvalidLabel { index, step } => {
+                write!(f, "step {index} {step:?} has an invalid label")
+            }
+            StepError::InvalidFocalLength { index, step } => {
+                write!(f, "step {index} {step:?} has an invalid focal length")
             }
-            v.push(Element { label, value });
+        }
+    }
+}
+
+impl std::error::Error for StepError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation<'a> {
+    /// Put the lens in its box, replacing the one with the same label
+    Insert {
+        label: &'a str,
+        focal_length: u16,
+    },
+    Remove {
+        label: &'a str,
+    },
+}
+
+impl<'a> Operation<'a> {
+    pub fn parse(index: usize, step: &'a str) -> Result<Self, StepError> {
+        let (label, operation) = if let Some(label) = step.strip_suffix('-') {
+            (label, Operation::Remove { label })
+        } else if let Some((label, focal_length)) = step.split_once('=') {
+            let focal_length = focal_length
+                .parse()
+                .map_err(|_| StepError::InvalidFocalLength {
+                    index,
+                    step: step.to_owned(),
+                })?;
+            (
+                label,
+                Operation::Insert {
+                    label,
+                    focal_length,
+                },
+            )
         } else {
-            let label = &w[0..w.len() - 1];
-            let hash = compute_hash(label);
-            let mut id = None;
-            for (i, e) in hashmap[hash as usize].iter().enumerate() {
-                if e.label == label {
-                    id = Some(i);
-                    break;
+            return Err(StepError::MissingOperation {
+                index,
+                step: step.to_owned(),
+            });
+        };
+        if label.is_empty() || label.contains(['=', '-']) {
+            return Err(StepError::InvalidLabel {
+                index,
+                step: step.to_owned(),
+            });
+        }
+        Ok(operation)
+    }
+
+    pub fn label(&self) -> &'a str {
+        match self {
+            Operation::Insert { label, .. } | Operation::Remove { label } => label,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lens<'a> {
+    pub label: &'a str,
+    pub focal_length: u16,
+}
+
+/// The 256 boxes, each lens keeps the serial number of its insertion so that the lenses of a
+/// box stay ordered without shifting them around on removal
+#[derive(Debug, Clone)]
+pub struct LensBoxes<'a> {
+    boxes: Vec<BTreeMap<u64, Lens<'a>>>,
+    serials: HashMap<&'a str, u64>,
+    next_serial: u64,
+}
+
+impl Default for LensBoxes<'_> {
+    fn default() -> Self {
+        Self {
+            boxes: vec![BTreeMap::new(); 256],
+            serials: HashMap::new(),
+            next_serial: 0,
+        }
+    }
+}
+
+impl<'a> LensBoxes<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn apply(&mut self, operation: Operation<'a>) {
+        let label = operation.label();
+        let lenses = &mut self.boxes[compute_hash(label) as usize];
+        match operation {
+            Operation::Insert { focal_length, .. } => {
+                let serial = *self.serials.entry(label).or_insert_with(|| {
+                    self.next_serial += 1;
+                    self.next_serial
+                });
+                lenses.insert(
+                    serial,
+                    Lens {
+                        label,
+                        focal_length,
+                    },
+                );
+            }
+            Operation::Remove { .. } => {
+                if let Some(serial) = self.serials.remove(label) {
+                    lenses.remove(&serial);
                 }
             }
-            if let Some(i) = id {
-                hashmap.get_mut(hash as usize).unwrap().remove(i);
+        }
+    }
+
+    /// Parse and apply one step, the boxes are left untouched on error
+    pub fn step(&mut self, index: usize, step: &'a str) -> Result<(), StepError> {
+        self.apply(Operation::parse(index, step)?);
+        Ok(())
+    }
+
+    /// Apply all the steps, calling `visit` with the index of each step and the boxes after it
+    pub fn replay(
+        steps: &[&'a str],
+        mut visit: impl FnMut(usize, &Self),
+    ) -> Result<Self, StepError> {
+        let mut boxes = Self::new();
+        for (index, step) in steps.iter().enumerate() {
+            boxes.step(index, step)?;
+            visit(index, &boxes);
+        }
+        Ok(boxes)
+    }
+
+    /// Lenses of the box, from front to back
+    pub fn contents(&self, box_id: u8) -> impl Iterator<Item = Lens<'a>> + '_ {
+        self.boxes[box_id as usize].values().copied()
+    }
+
+    pub fn focusing_power(&self) -> u64 {
+        self.boxes
+            .iter()
+            .enumerate()
+            .map(|(i, lenses)| {
+                lenses
+                    .values()
+                    .enumerate()
+                    .map(|(j, lens)| (1 + i as u64) * (1 + j as u64) * lens.focal_length as u64)
+                    .sum::<u64>()
+            })
+            .sum()
+    }
+}
+
+/// Non-empty boxes, one per line, in the format of the puzzle statement
+impl Display for LensBoxes<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, lenses) in self.boxes.iter().enumerate() {
+            if lenses.is_empty() {
+                continue;
             }
+            write!(f, "Box {i}:")?;
+            for lens in lenses.values() {
+                write!(f, " [{} {}]", lens.label, lens.focal_length)?;
+            }
+            writeln!(f)?;
         }
+        Ok(())
     }
-    hashmap
-        .iter()
-        .enumerate()
-        .map(|(i, b)| {
-            b.iter()
-                .enumerate()
-                .map(|(j, e)| (1 + i as u32) * (1 + j as u32) * e.value as u32)
-                .sum::<u32>()
-        })
-        .sum()
+}
+
+pub fn part2(input: &Type) -> u64 {
+    LensBoxes::replay(input, |_, _| {})
+        .unwrap()
+        .focusing_power()
 }
 
 #[cfg(test)]
@@ -82,4 +231,53 @@ mod tests {
         assert_eq!(part1(&generator(example)), 1320);
         assert_eq!(part2(&generator(example)), 145);
     }
+
+    #[test]
+    fn test_lens_boxes() {
+        let steps = generator(b"rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7");
+        let mut trace = vec![];
+        let boxes = LensBoxes::replay(&steps, |index, boxes| {
+            trace.push((index, boxes.focusing_power(), boxes.to_string()))
+        })
+        .unwrap();
+        assert_eq!(trace.len(), steps.len());
+        assert_eq!(trace[0], (0, 1, "Box 0: [rn 1]\n".to_owned()));
+        assert_eq!(trace[4].2, "Box 0: [rn 1] [cm 2]\n");
+        assert_eq!(trace[8].2, "Box 0: [rn 1] [cm 2]\nBox 3: [ot 9] [ab 5]\n");
+        assert_eq!(
+            boxes.to_string(),
+            "Box 0: [rn 1] [cm 2]\nBox 3: [ot 7] [ab 5] [pc 6]\n"
+        );
+        assert_eq!(trace[10].1, 145);
+        assert_eq!(
+            boxes.contents(3).map(|l| l.label).collect::<Vec<_>>(),
+            vec!["ot", "ab", "pc"]
+        );
+        assert_eq!(boxes.contents(1).count(), 0);
+
+        let mut boxes = LensBoxes::new();
+        boxes.step(0, "rn=1").unwrap();
+        assert_eq!(
+            boxes.step(1, "rn=x"),
+            Err(StepError::InvalidFocalLength {
+                index: 1,
+                step: "rn=x".to_owned()
+            })
+        );
+        assert!(matches!(
+            boxes.step(2, "rn"),
+            Err(StepError::MissingOperation { index: 2, .. })
+        ));
+        assert!(matches!(
+            boxes.step(3, "=4"),
+            Err(StepError::InvalidLabel { .. })
+        ));
+        assert!(matches!(
+            boxes.step(4, "a-b=4"),
+            Err(StepError::InvalidLabel { .. })
+        ));
+        assert!(boxes.step(5, "rn=99999999").is_err());
+        assert_eq!(boxes.focusing_power(), 1);
+        assert!(LensBoxes::replay(&["rn=1", "", "cm-"], |_, _| {}).is_err());
+    }
 }
@@ -1,5 +1,7 @@
 use std::str::from_utf8;
 
+use crate::helper::graph::{Graph, NodeId};
+
 pub fn generator(input: &[u8]) -> Vec<&[u8]> {
     let input = from_utf8(input).unwrap();
 
@@ -136,7 +138,7 @@ fn find_n_energized(
     energized.into_iter().map(|b| b.count_ones() as usize).sum()
 }
 
-pub fn part2(input: &[&[u8]]) -> usize {
+pub fn part2_simulation(input: &[&[u8]]) -> usize {
     let h = input.len();
     let w = input[0].len();
 
@@ -168,6 +170,208 @@ pub fn part2(input: &[&[u8]]) -> usize {
     max
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// What a tile does to a beam going in a direction
+enum Turn {
+    Continue(Direction),
+    Split,
+}
+
+fn turn(tile: u8, direction: Direction) -> Turn {
+    use Direction::*;
+    match (tile, direction) {
+        (b'/', Right) | (b'\\', Left) => Turn::Continue(Up),
+        (b'/', Left) | (b'\\', Right) => Turn::Continue(Down),
+        (b'/', Down) | (b'\\', Up) => Turn::Continue(Left),
+        (b'/', Up) | (b'\\', Down) => Turn::Continue(Right),
+        (b'|', Left | Right) => Turn::Split,
+        (b'-', Up | Down) => Turn::Split,
+        (b'.' | b'|' | b'-', _) => Turn::Continue(direction),
+        _ => panic!("unexpected tile {:?}", tile as char),
+    }
+}
+
+/// Every splitter is a node, linked to the splitters that the two beams it sends out when hit on
+/// its flat side activate. Tiles energized from each strongly connected component of splitters
+/// are computed once, so any beam is resolved by following it to the first splitter it activates.
+#[derive(Debug, Clone)]
+pub struct BeamGraph<'a> {
+    grid: &'a [&'a [u8]],
+    width: usize,
+    height: usize,
+    /// Component of the splitter on each tile, `NOT_SPLITTER` elsewhere
+    component: Vec<NodeId>,
+    /// Mask of the tiles energized once any splitter of the component is activated
+    reach: Vec<Vec<u64>>,
+}
+
+const NOT_SPLITTER: NodeId = NodeId::MAX;
+
+impl<'a> BeamGraph<'a> {
+    pub fn new(grid: &'a [&'a [u8]]) -> Self {
+        let height = grid.len();
+        let width = grid.first().map_or(0, |l| l.len());
+        let mut beams = Self {
+            grid,
+            width,
+            height,
+            component: vec![NOT_SPLITTER; width * height],
+            reach: vec![],
+        };
+
+        let mut splitters = vec![];
+        for (r, line) in grid.iter().enumerate() {
+            assert_eq!(line.len(), width, "grid rows have different lengths");
+            for (c, tile) in line.iter().enumerate() {
+                if matches!(tile, b'|' | b'-') {
+                    beams.component[r * width + c] = splitters.len() as NodeId;
+                    splitters.push((r, c));
+                }
+            }
+        }
+
+        let mut graph = Graph::with_nodes(splitters.len());
+        let mut own = vec![];
+        for (node, &(r, c)) in splitters.iter().enumerate() {
+            let mut mask = beams.empty_mask();
+            mask[(r * width + c) / 64] |= 1 << ((r * width + c) % 64);
+            let outputs = if grid[r][c] == b'|' {
+                [Direction::Up, Direction::Down]
+            } else {
+                [Direction::Left, Direction::Right]
+            };
+            for direction in outputs {
+                let Some(start) = beams.step((r, c), direction) else {
+                    continue;
+                };
+                if let Some((r, c)) = beams.trace(start, direction, Some((r, c)), &mut mask) {
+                    graph.add_edge(node as NodeId, beams.component[r * width + c], ());
+                }
+            }
+            own.push(mask);
+        }
+
+        // the components come sinks first, so the components they lead to are already done
+        let components = graph.strongly_connected_components();
+        let mut node_component = vec![0; splitters.len()];
+        for (id, nodes) in components.iter().enumerate() {
+            let mut reach = beams.empty_mask();
+            for &node in nodes {
+                node_component[node as usize] = id;
+                or_assign(&mut reach, &own[node as usize]);
+            }
+            for &node in nodes {
+                for &(child, _) in graph.children(node) {
+                    let child = node_component[child as usize];
+                    if child != id {
+                        or_assign(&mut reach, &beams.reach[child]);
+                    }
+                }
+            }
+            beams.reach.push(reach);
+        }
+        for (node, &(r, c)) in splitters.iter().enumerate() {
+            beams.component[r * width + c] = node_component[node] as NodeId;
+        }
+        beams
+    }
+
+    fn empty_mask(&self) -> Vec<u64> {
+        vec![0; (self.width * self.height).div_ceil(64)]
+    }
+
+    fn step(&self, (r, c): (usize, usize), direction: Direction) -> Option<(usize, usize)> {
+        let next = match direction {
+            Direction::Up => (r.wrapping_sub(1), c),
+            Direction::Down => (r + 1, c),
+            Direction::Left => (r, c.wrapping_sub(1)),
+            Direction::Right => (r, c + 1),
+        };
+        (next.0 < self.height && next.1 < self.width).then_some(next)
+    }
+
+    /// Energize the tiles from `position` until the beam activates a splitter, which is
+    /// returned, leaves the grid, reaches `stop` or loops back on itself
+    fn trace(
+        &self,
+        mut position: (usize, usize),
+        mut direction: Direction,
+        stop: Option<(usize, usize)>,
+        mask: &mut [u64],
+    ) -> Option<(usize, usize)> {
+        let start = (position, direction);
+        loop {
+            let id = position.0 * self.width + position.1;
+            mask[id / 64] |= 1 << (id % 64);
+            match turn(self.grid[position.0][position.1], direction) {
+                Turn::Split => return Some(position),
+                Turn::Continue(next) => direction = next,
+            }
+            position = self.step(position, direction)?;
+            if Some(position) == stop || (position, direction) == start {
+                return None;
+            }
+        }
+    }
+
+    /// Mask of the tiles energized by a beam entering `position` going in `direction`, bit
+    /// `row * width + column` being set for an energized tile
+    pub fn energized(&self, position: (usize, usize), direction: Direction) -> Vec<u64> {
+        assert!(position.0 < self.height && position.1 < self.width);
+        let mut mask = self.empty_mask();
+        if let Some((r, c)) = self.trace(position, direction, None, &mut mask) {
+            or_assign(
+                &mut mask,
+                &self.reach[self.component[r * self.width + c] as usize],
+            );
+        }
+        mask
+    }
+
+    pub fn count_energized(&self, position: (usize, usize), direction: Direction) -> usize {
+        self.energized(position, direction)
+            .iter()
+            .map(|b| b.count_ones() as usize)
+            .sum()
+    }
+
+    /// All the beams entering from the border, with their position and direction
+    pub fn border_entries(&self) -> impl Iterator<Item = ((usize, usize), Direction)> {
+        let (h, w) = (self.height, self.width);
+        (0..h)
+            .flat_map(move |r| [((r, 0), Direction::Right), ((r, w - 1), Direction::Left)])
+            .chain(
+                (0..w).flat_map(move |c| [((0, c), Direction::Down), ((h - 1, c), Direction::Up)]),
+            )
+    }
+}
+
+fn or_assign(mask: &mut [u64], other: &[u64]) {
+    mask.iter_mut().zip(other).for_each(|(a, b)| *a |= b);
+}
+
+/// Energized mask of a beam entering the grid at `position` going in `direction`, see
+/// `BeamGraph::energized`
+pub fn energized_mask(grid: &[&[u8]], position: (usize, usize), direction: Direction) -> Vec<u64> {
+    BeamGraph::new(grid).energized(position, direction)
+}
+
+pub fn part2(input: &[&[u8]]) -> usize {
+    let beams = BeamGraph::new(input);
+    beams
+        .border_entries()
+        .map(|(position, direction)| beams.count_energized(position, direction))
+        .max()
+        .unwrap_or(0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -191,4 +395,47 @@ mod tests {
         assert_eq!(part1(&generator(example)), 46);
         assert_eq!(part2(&generator(example)), 51);
     }
+
+    #[test]
+    fn test_energized() {
+        let to_tuple = |direction| match direction {
+            Direction::Up => (usize::MAX, 0),
+            Direction::Down => (1, 0),
+            Direction::Left => (0, usize::MAX),
+            Direction::Right => (0, 1),
+        };
+        let input = generator(include_bytes!("../inputs/day_16.txt"));
+        let beams = BeamGraph::new(&input);
+        assert_eq!(beams.border_entries().count(), 4 * input.len());
+        // from every tile of some rows, including loops entered in the middle
+        for r in [0, 1, 37, 64, input.len() - 1] {
+            for c in 0..input[0].len() {
+                for direction in [
+                    Direction::Up,
+                    Direction::Down,
+                    Direction::Left,
+                    Direction::Right,
+                ] {
+                    assert_eq!(
+                        beams.count_energized((r, c), direction),
+                        find_n_energized((r, c), to_tuple(direction), &input),
+                        "{r} {c} {direction:?}"
+                    );
+                }
+            }
+        }
+
+        // a loop of mirrors without any splitter
+        let grid = generator(b"/-\\\n|./\n\\-/\n");
+        let mask = energized_mask(&grid, (1, 1), Direction::Right);
+        assert_eq!(mask, vec![0b111_111_111]);
+        let grid = generator(b"/.\\\n...\n\\./\n");
+        let mask = energized_mask(&grid, (0, 1), Direction::Right);
+        assert_eq!(mask, vec![0b111_101_111]);
+        assert_eq!(
+            energized_mask(&grid, (1, 0), Direction::Up),
+            vec![0b111_101_111]
+        );
+        assert_eq!(energized_mask(&grid, (0, 1), Direction::Up), vec![0b10]);
+    }
 }
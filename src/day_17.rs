@@ -1,34 +1,20 @@
-use std::{cmp::Ordering, collections::BinaryHeap};
+use arrayvec::ArrayVec;
 
-type Input<'a> = Vec<&'a [u8]>;
-
-#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Hash, Clone, Copy)]
-enum Direction {
-    North,
-    East,
-    None,
-    South,
-    West,
-}
+use crate::helper::search::{dijkstra_buckets, DenseIndex};
 
-#[derive(PartialEq, Eq, Debug)]
-struct State {
-    heat_loss: u64,
-    line: usize,
-    col: usize,
-    direction: Direction,
-    n_direction: u8,
-}
+type Input<'a> = Vec<&'a [u8]>;
 
-impl Ord for State {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.heat_loss.cmp(&other.heat_loss).reverse()
-    }
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Crucible {
+    // line * width + col
+    position: u32,
+    // axis of the last run, the next one has to be on the other axis
+    vertical: bool,
 }
 
-impl PartialOrd for State {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl DenseIndex for Crucible {
+    fn index(&self) -> usize {
+        self.position as usize * 2 + self.vertical as usize
     }
 }
 
@@ -37,179 +23,57 @@ pub fn generator(input: &[u8]) -> Input {
 }
 
 pub fn part1(input: &Input) -> u64 {
-    let h = input.len();
-    let w = input[0].len();
-    let mut queue = BinaryHeap::new();
-
-    // 141 ** 2 * 5 * 3 / 8
-    let mut seen = vec![0u8; 37277];
-    queue.push(State {
-        heat_loss: 0,
-        line: 0,
-        col: 0,
-        direction: Direction::South,
-        n_direction: 0,
-    });
-    'out: while let Some(e) = queue.pop() {
-        if e.n_direction > 3 {
-            continue;
-        }
-        if e.line == h - 1 && e.col == w - 1 {
-            return e.heat_loss;
-        }
-        let seen_id =
-            ((e.line * 141 + e.col) * 5 + e.direction as usize) * 3 + e.n_direction as usize;
-        if (seen[seen_id / 8] >> (seen_id % 8)) & 1 != 0 {
-            continue 'out;
-        }
-        seen[seen_id / 8] |= 1 << (seen_id % 8);
-        if e.col <= w - 2 && e.direction != Direction::East {
-            queue.push(State {
-                heat_loss: e.heat_loss + (input[e.line][e.col + 1] - b'0') as u64,
-                line: e.line,
-                col: e.col + 1,
-                direction: Direction::West,
-                n_direction: if e.direction == Direction::West {
-                    e.n_direction + 1
-                } else {
-                    1
-                },
-            });
-        }
-        if e.col > 0 && e.direction != Direction::West {
-            queue.push(State {
-                heat_loss: e.heat_loss + (input[e.line][e.col.wrapping_sub(1)] - b'0') as u64,
-                line: e.line,
-                col: e.col.wrapping_sub(1),
-                direction: Direction::East,
-                n_direction: if e.direction == Direction::East {
-                    e.n_direction + 1
-                } else {
-                    1
-                },
-            });
-        }
-        if e.line <= h - 2 && e.direction != Direction::North {
-            queue.push(State {
-                heat_loss: e.heat_loss + (input[e.line + 1][e.col] - b'0') as u64,
-                line: e.line + 1,
-                col: e.col,
-                direction: Direction::South,
-                n_direction: if e.direction == Direction::South {
-                    e.n_direction + 1
-                } else {
-                    1
-                },
-            });
-        }
-        if e.line > 0 && e.direction != Direction::South {
-            queue.push(State {
-                heat_loss: e.heat_loss + (input[e.line.wrapping_sub(1)][e.col] - b'0') as u64,
-                line: e.line.wrapping_sub(1),
-                col: e.col,
-                direction: Direction::North,
-                n_direction: if e.direction == Direction::North {
-                    e.n_direction + 1
-                } else {
-                    1
-                },
-            });
-        }
-    }
-
-    0
+    min_heat_loss(input, 1, 3)
 }
 
 pub fn part2(input: &Input) -> u64 {
+    min_heat_loss(input, 4, 10)
+}
+
+/// Each state is the end of a run of `min_run` to `max_run` blocks, so there is no need to
+/// count the blocks already travelled in the current direction
+fn min_heat_loss(input: &Input, min_run: usize, max_run: usize) -> u64 {
     let h = input.len();
     let w = input[0].len();
-    let mut queue = BinaryHeap::with_capacity(141 * 141 * 2);
-    let mut seen = vec![0u8; 7456];
-    // WIP merge direction NS and EW. Only use North and East
-    queue.push(State {
-        heat_loss: 0,
-        line: 0,
-        col: 0,
-        direction: Direction::None,
-        n_direction: 0,
+    let starts = [false, true].map(|vertical| Crucible {
+        position: 0,
+        vertical,
     });
-    'out: while let Some(e) = queue.pop() {
-        if e.line == h - 1 && e.col == w - 1 {
-            return e.heat_loss;
-        }
-        let seen_id = (e.line * 141 + e.col) * 3 + e.direction as usize;
-        if (seen[seen_id / 8] >> (seen_id % 8)) & 1 != 0 {
-            continue 'out;
-        }
-        seen[seen_id / 8] |= 1 << (seen_id % 8);
-        if e.col <= w - 5 && e.direction != Direction::East {
-            let mut add_heat: u64 = (1..4)
-                .map(|i| (input[e.line][e.col + i] - b'0') as u64)
-                .sum();
-
-            for c in (e.col + 4)..(e.col + 11).min(w) {
-                add_heat += (input[e.line][c] - b'0') as u64;
-                queue.push(State {
-                    heat_loss: e.heat_loss + add_heat,
-                    line: e.line,
-                    col: c,
-                    direction: Direction::East,
-                    n_direction: 0,
-                });
-            }
-        }
-        if e.col > 3 && e.direction != Direction::East {
-            let mut add_heat: u64 = (1..4)
-                .map(|i| (input[e.line][e.col - i] - b'0') as u64)
-                .sum();
 
-            for c in (e.col.saturating_sub(10)..=(e.col - 4)).rev() {
-                add_heat += (input[e.line][c] - b'0') as u64;
-                queue.push(State {
-                    heat_loss: e.heat_loss + add_heat,
-                    line: e.line,
-                    col: c,
-                    direction: Direction::East,
-                    n_direction: 0,
-                });
-            }
-        }
-        if e.line <= h - 5 && e.direction != Direction::North {
-            let mut add_heat: u64 = (1..4)
-                .map(|i| (input[e.line + i][e.col] - b'0') as u64)
-                .sum();
-
-            for c in (e.line + 4)..(e.line + 11).min(h) {
-                add_heat += (input[c][e.col] - b'0') as u64;
-                queue.push(State {
-                    heat_loss: e.heat_loss + add_heat,
-                    line: c,
-                    col: e.col,
-                    direction: Direction::North,
-                    n_direction: 0,
-                });
-            }
-        }
-        if e.line > 3 && e.direction != Direction::North {
-            let mut add_heat: u64 = (1..4)
-                .map(|i| (input[e.line - i][e.col] - b'0') as u64)
-                .sum();
-
-            for c in (e.line.saturating_sub(10)..=(e.line - 4)).rev() {
-                add_heat += (input[c][e.col] - b'0') as u64;
-                queue.push(State {
-                    heat_loss: e.heat_loss + add_heat,
-                    line: c,
-                    col: e.col,
-                    direction: Direction::North,
-                    n_direction: 0,
-                });
+    let neighbors = |crucible: &Crucible| {
+        let line = crucible.position as usize / w;
+        let col = crucible.position as usize % w;
+        let mut out = ArrayVec::<_, 40>::new();
+        for delta in [-1isize, 1] {
+            let mut heat_loss = 0;
+            for run in 1..=max_run as isize {
+                let (l, c) = if crucible.vertical {
+                    (line, col.wrapping_add_signed(run * delta))
+                } else {
+                    (line.wrapping_add_signed(run * delta), col)
+                };
+                if l >= h || c >= w {
+                    break;
+                }
+                heat_loss += (input[l][c] - b'0') as usize;
+                if run as usize >= min_run {
+                    let next = Crucible {
+                        position: (l * w + c) as u32,
+                        vertical: l != line,
+                    };
+                    out.push((next, heat_loss));
+                }
             }
         }
-    }
+        out
+    };
 
-    0
+    dijkstra_buckets(starts, h * w * 2, neighbors, |c| {
+        c.position as usize == h * w - 1
+    })
+    .map_or(0, |path| path.cost as u64)
 }
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,5 +1,7 @@
 use std::str::from_utf8;
 
+use crate::helper::geometry::{boundary_points, interior_points};
+
 pub fn generator(input: &[u8]) -> &[u8] {
     input
 }
@@ -42,8 +44,7 @@ pub fn part2(input: &[u8]) -> u64 {
 
 fn area(iter: impl Iterator<Item = (u8, i64)>) -> u64 {
     let mut current_position = (0, 0);
-    let mut n_points_on_side = 0;
-    let mut partial_area = 0;
+    let mut vertices = vec![current_position];
     iter.for_each(|(direction, n)| {
         let direction = match direction {
             b'U' => (0, -1),
@@ -56,16 +57,12 @@ fn area(iter: impl Iterator<Item = (u8, i64)>) -> u64 {
             0 => (1, 0),
             _ => unreachable!(),
         };
-        if direction.1 == 0 {
-            // Shoelace formula
-            partial_area += current_position.1 * direction.0 * n;
-        }
         current_position.0 += direction.0 * n;
         current_position.1 += direction.1 * n;
-        n_points_on_side += n;
+        vertices.push(current_position);
     });
-    // Pick's theorem
-    partial_area.unsigned_abs() + n_points_on_side as u64 / 2 + 1
+    // Shoelace formula and Pick's theorem
+    (interior_points(&vertices).unwrap() + boundary_points(&vertices).unwrap()) as u64
 }
 
 #[cfg(test)]
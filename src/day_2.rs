@@ -1,85 +1,238 @@
-use std::str::from_utf8;
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    io::{self, BufRead},
+    str::from_utf8,
+};
 
-type Int = u32;
+type Int = u64;
 
-#[derive(Default)]
-struct Colors {
-    red: u8,
-    green: u8,
-    blue: u8,
+/// Number of cubes of each color
+pub type Bag<'a> = BTreeMap<&'a str, Int>;
+
+pub const DEFAULT_PALETTE: &[&str] = &["red", "green", "blue"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game<'a> {
+    pub id: Int,
+    pub sets: Vec<Bag<'a>>,
 }
 
-pub struct Game {
-    id: u8,
-    colors: Vec<Colors>,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    UnknownColor { line: usize, color: String },
+    Malformed { line: usize, text: String },
 }
 
-pub fn generator(input: &[u8]) -> Vec<Game> {
-    from_utf8(input)
-        .unwrap()
-        .lines()
-        .map(|line| {
-            let (game, sets) = line.split_once(": ").unwrap();
-            let id = game[5..].parse().unwrap();
-            let colors = sets
-                .split("; ")
-                .map(|set| {
-                    let mut colors = Colors::default();
-                    for ind_color in set.split(", ") {
-                        let (n, color_str) = ind_color.split_once(' ').unwrap();
-                        if color_str == "blue" {
-                            colors.blue = n.parse().unwrap();
-                        }
-                        if color_str == "red" {
-                            colors.red = n.parse().unwrap();
-                        }
-                        if color_str == "green" {
-                            colors.green = n.parse().unwrap();
-                        }
-                    }
-                    colors
-                })
-                .collect();
-            Game { id, colors }
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::UnknownColor { line, color } => {
+                write!(f, "line {line}: unknown color {color:?}")
+            }
+            ParseError::Malformed { line, text } => write!(f, "line {line}: can't parse {text:?}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parse one line like "Game 1: 3 blue, 4 red; 1 red", `line` is only used in errors
+pub fn parse_game<'a>(
+    line: usize,
+    text: &'a str,
+    palette: &[&str],
+) -> Result<Game<'a>, ParseError> {
+    let malformed = |text: &str| ParseError::Malformed {
+        line,
+        text: text.to_owned(),
+    };
+    let (game, sets) = text.split_once(": ").ok_or_else(|| malformed(text))?;
+    let id = game
+        .strip_prefix("Game ")
+        .and_then(|id| id.parse().ok())
+        .ok_or_else(|| malformed(game))?;
+    let sets = sets
+        .split("; ")
+        .map(|set| {
+            let mut bag = Bag::new();
+            for ind_color in set.split(", ") {
+                let (n, color) = ind_color
+                    .split_once(' ')
+                    .ok_or_else(|| malformed(ind_color))?;
+                if !palette.contains(&color) {
+                    return Err(ParseError::UnknownColor {
+                        line,
+                        color: color.to_owned(),
+                    });
+                }
+                let n: Int = n.parse().map_err(|_| malformed(ind_color))?;
+                *bag.entry(color).or_default() += n;
+            }
+            Ok(bag)
         })
-        .collect()
+        .collect::<Result<_, _>>()?;
+    Ok(Game { id, sets })
 }
 
-pub fn part1(input: &[Game]) -> Int {
+/// Parse every line, line numbers in errors start at 1
+pub fn parse_games<'a>(input: &'a str, palette: &[&str]) -> Result<Vec<Game<'a>>, ParseError> {
     input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| parse_game(i + 1, line, palette))
+        .collect()
+}
+
+pub fn generator(input: &[u8]) -> Vec<Game<'_>> {
+    parse_games(from_utf8(input).unwrap(), DEFAULT_PALETTE).unwrap()
+}
+
+/// Fewest cubes of each color that make the game possible
+pub fn minimal_bag<'a>(game: &Game<'a>) -> Bag<'a> {
+    let mut bag = Bag::new();
+    for set in game.sets.iter() {
+        for (color, n) in set {
+            let max = bag.entry(*color).or_default();
+            *max = (*max).max(*n);
+        }
+    }
+    bag
+}
+
+pub fn minimal_bags<'a>(games: &[Game<'a>]) -> Vec<Bag<'a>> {
+    games.iter().map(minimal_bag).collect()
+}
+
+/// A game is possible if no color is needed more than available in the bag.
+/// Colors missing from the bag are not available at all.
+pub fn is_possible(game: &Game, bag: &Bag) -> bool {
+    minimal_bag(game)
         .iter()
-        .filter_map(|game| {
-            if (game.colors.iter().map(|c| c.red).max().unwrap_or_default() <= 12)
-                && (game
-                    .colors
-                    .iter()
-                    .map(|c| c.green)
-                    .max()
-                    .unwrap_or_default()
-                    <= 13)
-                && (game.colors.iter().map(|c| c.blue).max().unwrap_or_default() <= 14)
-            {
-                Some(game.id as Int)
-            } else {
-                None
-            }
-        })
+        .all(|(color, n)| *n <= bag.get(color).copied().unwrap_or_default())
+}
+
+/// Sum of the ids of the games possible with the given bag
+pub fn possible_ids_sum(games: &[Game], bag: &Bag) -> Int {
+    games
+        .iter()
+        .filter(|game| is_possible(game, bag))
+        .map(|game| game.id)
         .sum()
 }
 
+/// Product of the needed number of cubes of each color of the palette
+pub fn power(bag: &Bag, palette: &[&str]) -> Int {
+    palette
+        .iter()
+        .map(|color| bag.get(color).copied().unwrap_or_default())
+        .product()
+}
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    Parse(ParseError),
+}
+
+impl Display for StreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamError::Io(e) => write!(f, "{e}"),
+            StreamError::Parse(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+/// Read games line by line and yield `(id, minimal bag)`, where the minimal bag has one count
+/// per color of the palette, in the same order. Only the current line is kept in memory.
+pub struct GameStream<'p, R> {
+    reader: R,
+    palette: &'p [&'p str],
+    line: String,
+    line_number: usize,
+}
+
+impl<'p, R: BufRead> GameStream<'p, R> {
+    pub fn new(reader: R, palette: &'p [&'p str]) -> Self {
+        Self {
+            reader,
+            palette,
+            line: String::new(),
+            line_number: 0,
+        }
+    }
+}
+
+impl<'p, R: BufRead> Iterator for GameStream<'p, R> {
+    type Item = Result<(Int, Vec<Int>), StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.line.clear();
+        match self.reader.read_line(&mut self.line) {
+            Ok(0) => return None,
+            Ok(_) => {}
+            Err(e) => return Some(Err(StreamError::Io(e))),
+        }
+        self.line_number += 1;
+        let text = self.line.trim_end_matches(['\n', '\r']);
+        let game = match parse_game(self.line_number, text, self.palette) {
+            Ok(game) => game,
+            Err(e) => return Some(Err(StreamError::Parse(e))),
+        };
+        let bag = minimal_bag(&game);
+        let needed = self
+            .palette
+            .iter()
+            .map(|color| bag.get(color).copied().unwrap_or_default())
+            .collect();
+        Some(Ok((game.id, needed)))
+    }
+}
+
+/// Answers of both parts for a stream of games
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Totals {
+    pub possible_ids_sum: Int,
+    pub power_sum: Int,
+}
+
+/// Same as `possible_ids_sum` and `power` summed over the games, without storing them
+pub fn stream_totals(
+    reader: impl BufRead,
+    palette: &[&str],
+    bag: &Bag,
+) -> Result<Totals, StreamError> {
+    let available = palette
+        .iter()
+        .map(|color| bag.get(color).copied().unwrap_or_default())
+        .collect::<Vec<_>>();
+    let mut totals = Totals::default();
+    for game in GameStream::new(reader, palette) {
+        let (id, needed) = game?;
+        if needed.iter().zip(&available).all(|(n, a)| n <= a) {
+            totals.possible_ids_sum += id;
+        }
+        totals.power_sum += needed.iter().product::<Int>();
+    }
+    Ok(totals)
+}
+
+/// Bag of part1
+pub fn elf_bag() -> Bag<'static> {
+    Bag::from([("red", 12), ("green", 13), ("blue", 14)])
+}
+
+pub fn part1(input: &[Game]) -> Int {
+    possible_ids_sum(input, &elf_bag())
+}
+
 pub fn part2(input: &[Game]) -> Int {
     input
         .iter()
-        .map(|game| {
-            game.colors.iter().map(|c| c.red).max().unwrap_or_default() as Int
-                * (game
-                    .colors
-                    .iter()
-                    .map(|c| c.green)
-                    .max()
-                    .unwrap_or_default() as Int)
-                * (game.colors.iter().map(|c| c.blue).max().unwrap_or_default() as Int)
-        })
+        .map(|game| power(&minimal_bag(game), DEFAULT_PALETTE))
         .sum()
 }
 
@@ -102,4 +255,73 @@ mod tests {
 
         assert_eq!(part2(&generator(example)), 2286);
     }
+
+    #[test]
+    fn test_stream() {
+        let input = &include_bytes!("../inputs/day_2.txt")[..];
+        let totals = stream_totals(input, DEFAULT_PALETTE, &elf_bag()).unwrap();
+        assert_eq!(
+            totals,
+            Totals {
+                possible_ids_sum: 1853,
+                power_sum: 72706
+            }
+        );
+
+        let example = "Game 1: 3 blue, 4 red; 1 red, 2 green\r\nGame 2: 1 blue\n".as_bytes();
+        let games = GameStream::new(example, DEFAULT_PALETTE)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(games, vec![(1, vec![4, 2, 3]), (2, vec![0, 0, 1])]);
+
+        let example = "Game 1: 3 blue\nGame 2: 1 pink\nGame 3: 1 red".as_bytes();
+        let mut stream = GameStream::new(example, DEFAULT_PALETTE);
+        assert!(stream.next().unwrap().is_ok());
+        assert!(matches!(
+            stream.next(),
+            Some(Err(StreamError::Parse(ParseError::UnknownColor {
+                line: 2,
+                ..
+            })))
+        ));
+        assert!(matches!(
+            stream_totals(b"Game 1: 3 bl\xffe".as_slice(), DEFAULT_PALETTE, &elf_bag()),
+            Err(StreamError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_colors() {
+        let example = "Game 1: 3 blue, 4 red; 1 red, 2 yellow, 6 blue; 2 yellow\n\
+            Game 2: 1 blue, 2 yellow; 3 yellow, 40 blue, 1 red\n\
+            Game 30: 5 red, 1 blue";
+        let palette = ["red", "blue", "yellow"];
+        let games = parse_games(example, &palette).unwrap();
+        assert_eq!(games[2].id, 30);
+
+        let bags = minimal_bags(&games);
+        assert_eq!(bags[0], Bag::from([("blue", 6), ("red", 4), ("yellow", 2)]));
+        assert_eq!(power(&bags[1], &palette), 40 * 3);
+
+        let bag = Bag::from([("red", 5), ("blue", 6), ("yellow", 2)]);
+        assert_eq!(possible_ids_sum(&games, &bag), 31);
+        // no yellow cube available
+        let bag = Bag::from([("red", 5), ("blue", 6)]);
+        assert_eq!(possible_ids_sum(&games, &bag), 30);
+
+        assert_eq!(
+            parse_games(example, DEFAULT_PALETTE),
+            Err(ParseError::UnknownColor {
+                line: 1,
+                color: "yellow".to_owned()
+            })
+        );
+        assert_eq!(
+            parse_games("Game 1: 3 blue\nGame 2: x red", DEFAULT_PALETTE),
+            Err(ParseError::Malformed {
+                line: 2,
+                text: "x red".to_owned()
+            })
+        );
+    }
 }
@@ -3,6 +3,8 @@ use std::collections::VecDeque;
 use ahash::{HashMap, HashMapExt};
 use arrayvec::ArrayVec;
 
+use crate::helper::Interner;
+
 const N_NODE: usize = 59;
 
 #[derive(Debug, Clone)]
@@ -34,8 +36,7 @@ pub struct Graph {
 
 struct GraphBuilder<'a> {
     graph: Graph,
-    next_id: usize,
-    name_to_id: HashMap<&'a [u8], usize>,
+    names: Interner<'a, u16>,
 }
 
 impl Graph {
@@ -54,27 +55,18 @@ impl<'a> GraphBuilder<'a> {
     fn new() -> GraphBuilder<'a> {
         GraphBuilder {
             graph: Graph::new(),
-            next_id: 0,
-            name_to_id: HashMap::<&'a [u8], usize>::new(),
+            names: Interner::with_capacity(N_NODE),
         }
     }
 
     fn get_or_create_node_id(&mut self, name: &'a [u8]) -> usize {
-        self.name_to_id
-            .entry(name)
-            .or_insert_with(|| {
-                self.next_id += 1;
-                if name.len() == 10 {
-                    self.graph.nodes.push(Node::Broadcaster);
-                    self.graph.broadcaster_id = self.next_id - 1;
-                } else {
-                    self.graph.nodes.push(Node::Undef);
-                }
-                self.graph.parents.push(vec![]);
-                self.graph.children.push(vec![]);
-                self.next_id - 1
-            })
-            .to_owned()
+        let id = self.names.intern(name) as usize;
+        if id == self.graph.nodes.len() {
+            self.graph.nodes.push(Node::Undef);
+            self.graph.parents.push(vec![]);
+            self.graph.children.push(vec![]);
+        }
+        id
     }
 }
 
@@ -86,16 +78,12 @@ pub fn generator(input: &[u8]) -> Graph {
         .collect::<Vec<_>>();
 
     for line in lines {
-        let (t, line) = line.split_first().unwrap();
-
-        let node = if t == &b'%' {
-            Node::FlipFlop(FlipFlop { state: false })
-        } else if t == &b'&' {
-            Node::Conj(Conj {
+        let node = match line[0] {
+            b'%' => Node::FlipFlop(FlipFlop { state: false }),
+            b'&' => Node::Conj(Conj {
                 inputs: HashMap::new(),
-            })
-        } else {
-            Node::Undef
+            }),
+            _ => Node::Broadcaster,
         };
 
         let node_ids = line
@@ -117,8 +105,9 @@ pub fn generator(input: &[u8]) -> Graph {
         }
     }
 
-    let k = &[b'r', b'x'][..];
-    graph_builder.graph.rx_id = graph_builder.name_to_id.get(k).unwrap().to_owned();
+    let names = &graph_builder.names;
+    graph_builder.graph.broadcaster_id = names.get(b"broadcaster").unwrap() as usize;
+    graph_builder.graph.rx_id = names.get(b"rx").unwrap() as usize;
     graph_builder.graph
 }
 
@@ -1,31 +1,22 @@
-use ahash::{HashMap, HashMapExt};
-
 use rustworkx_core::petgraph::graphmap::UnGraphMap;
 
 use rustworkx_core::connectivity::stoer_wagner_min_cut;
 
+use crate::helper::Interner;
+
 type Input = Vec<(usize, usize)>;
 
 pub fn generator(input: &[u8]) -> Input {
-    let mut next_id = 0usize;
-    let mut name_to_id = HashMap::new();
+    let mut names = Interner::<u32>::new();
     let mut edges = vec![];
     for line in input[0..input.len() - 1].split(|b| b == &b'\n') {
-        let names = line
+        let ids = line
             .split(|b| !b.is_ascii_lowercase())
             .filter(|b| !b.is_empty())
-            .map(|b| {
-                name_to_id
-                    .entry(b)
-                    .or_insert_with(|| {
-                        next_id += 1;
-                        next_id - 1
-                    })
-                    .to_owned()
-            })
+            .map(|b| names.intern(b) as usize)
             .collect::<Vec<_>>();
-        let first = names.first().unwrap();
-        for other in names[1..].iter() {
+        let first = ids.first().unwrap();
+        for other in ids[1..].iter() {
             edges.push((*first, *other));
         }
     }
@@ -1,4 +1,7 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::Range,
+};
 
 type Map<'a> = Vec<&'a [u8]>;
 
@@ -36,6 +39,100 @@ fn find_gears(x: usize, y: usize, map: &Map) -> HashSet<(usize, usize)> {
     out
 }
 
+/// Anything that is neither a digit nor a '.'
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol {
+    pub line: usize,
+    pub column: usize,
+    pub value: u8,
+}
+
+/// A number of the schematic with the symbols around it, in reading order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartNumber {
+    pub value: u64,
+    pub line: usize,
+    pub columns: Range<usize>,
+    pub symbols: Vec<Symbol>,
+}
+
+fn is_symbol(b: u8) -> bool {
+    !b.is_ascii_digit() && b != b'.'
+}
+
+/// Every number of the map, including the ones without any adjacent symbol
+pub fn extract_parts(map: &Map) -> Vec<PartNumber> {
+    let mut parts = vec![];
+    for (x, line) in map.iter().enumerate() {
+        let mut y = 0;
+        while y < line.len() {
+            if !line[y].is_ascii_digit() {
+                y += 1;
+                continue;
+            }
+            let start = y;
+            let mut value = 0;
+            while y < line.len() && line[y].is_ascii_digit() {
+                value = value * 10 + (line[y] - b'0') as u64;
+                y += 1;
+            }
+
+            let mut symbols = vec![];
+            for (x, line) in map.iter().enumerate().take(x + 2).skip(x.saturating_sub(1)) {
+                for column in start.saturating_sub(1)..(y + 1).min(line.len()) {
+                    if is_symbol(line[column]) {
+                        symbols.push(Symbol {
+                            line: x,
+                            column,
+                            value: line[column],
+                        });
+                    }
+                }
+            }
+            parts.push(PartNumber {
+                value,
+                line: x,
+                columns: start..y,
+                symbols,
+            });
+        }
+    }
+    parts
+}
+
+/// Numbers adjacent to each symbol of the map, symbols without numbers included
+pub fn numbers_by_symbol(map: &Map, parts: &[PartNumber]) -> BTreeMap<Symbol, Vec<u64>> {
+    let mut out = BTreeMap::new();
+    for (x, line) in map.iter().enumerate() {
+        for (y, b) in line.iter().enumerate() {
+            if is_symbol(*b) {
+                out.insert(
+                    Symbol {
+                        line: x,
+                        column: y,
+                        value: *b,
+                    },
+                    vec![],
+                );
+            }
+        }
+    }
+    for part in parts {
+        for symbol in part.symbols.iter() {
+            out.get_mut(symbol).unwrap().push(part.value);
+        }
+    }
+    out
+}
+
+/// `symbol`s with exactly `n_numbers` adjacent numbers, with these numbers
+pub fn gears(map: &Map, symbol: u8, n_numbers: usize) -> Vec<(Symbol, Vec<u64>)> {
+    numbers_by_symbol(map, &extract_parts(map))
+        .into_iter()
+        .filter(|(s, numbers)| s.value == symbol && numbers.len() == n_numbers)
+        .collect()
+}
+
 pub fn generator(input: &[u8]) -> Vec<&[u8]> {
     input[0..input.len() - 1].split(|b| b == &b'\n').collect()
 }
@@ -71,6 +168,14 @@ pub fn part1(input: &Map) -> u64 {
     numbers.iter().sum()
 }
 
+pub fn part1_parts(input: &Map) -> u64 {
+    extract_parts(input)
+        .iter()
+        .filter(|part| !part.symbols.is_empty())
+        .map(|part| part.value)
+        .sum()
+}
+
 pub fn part2_hash(input: &Map) -> u64 {
     let mut number;
     let mut gear_to_numbers: HashMap<(usize, usize), Vec<u64>> = HashMap::new();
@@ -257,6 +362,13 @@ pub fn part2(input: &Map) -> u64 {
     s
 }
 
+pub fn part2_parts(input: &Map) -> u64 {
+    gears(input, b'*', 2)
+        .iter()
+        .map(|(_, numbers)| numbers.iter().product::<u64>())
+        .sum()
+}
+
 fn parse_left_right(input: &[&[u8]], x: usize, y: usize) -> u64 {
     match (
         {
@@ -345,4 +457,59 @@ mod tests {
         assert_eq!(part2(&generator(example)), 467835);
         assert_eq!(part2_hash(&generator(example)), 467835);
     }
+
+    #[test]
+    fn test_extract_parts() {
+        let example = "467..114..\n\
+            ...*......\n\
+            ..35..633.\n\
+            ......#...\n\
+            617*......\n\
+            .....+.58.\n\
+            ..592.....\n\
+            ......755.\n\
+            ...$.*....\n\
+            .664.598..\n"
+            .as_bytes();
+        let map = generator(example);
+        let parts = extract_parts(&map);
+        assert_eq!(parts.len(), 10);
+        let star = |line, column| Symbol {
+            line,
+            column,
+            value: b'*',
+        };
+        assert_eq!(
+            parts[0],
+            PartNumber {
+                value: 467,
+                line: 0,
+                columns: 0..3,
+                symbols: vec![star(1, 3)]
+            }
+        );
+        assert!(parts[1].symbols.is_empty());
+        assert_eq!(parts[4].symbols, vec![star(4, 3)]);
+
+        // a lone '*' next to 617 only
+        assert_eq!(gears(&map, b'*', 1), vec![(star(4, 3), vec![617])]);
+        assert_eq!(
+            gears(&map, b'*', 2)
+                .iter()
+                .map(|(s, _)| (s.line, s.column))
+                .collect::<Vec<_>>(),
+            vec![(1, 3), (8, 5)]
+        );
+        assert_eq!(gears(&map, b'#', 1)[0].1, vec![633]);
+        assert_eq!(gears(&map, b'$', 0), vec![]);
+        assert_eq!(gears(&map, b'$', 1)[0].1, vec![664]);
+
+        // numbers on the edges and a symbol touching 4 of them
+        let map = generator(b"1.22\n.&..\n3.44\n");
+        assert_eq!(gears(&map, b'&', 4)[0].1, vec![1, 22, 3, 44]);
+
+        let input = generator(include_bytes!("../inputs/day_3.txt"));
+        assert_eq!(part1_parts(&input), 507214);
+        assert_eq!(part2_parts(&input), 72553319);
+    }
 }
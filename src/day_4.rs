@@ -2,100 +2,164 @@ use std::str::from_utf8;
 
 use ahash::AHashSet;
 
-type Data = Vec<(AHashSet<u8>, AHashSet<u8>)>;
-
-pub fn generator(input: &[u8]) -> &str {
-    from_utf8(input).unwrap()
+/// Distinct numbers of a card. When they are all below 128 they are stored in a bitset,
+/// otherwise they are kept sorted and intersected with a merge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NumberSet {
+    Bits(u128),
+    Sorted(Vec<u32>),
 }
 
-pub fn generator_bitset(input: &str) -> Vec<u32> {
-    // number are all lower than 100. So instead of using a hashing function,
-    // we could set bit of a u128 to 1 if the number is present, and compute the
-    // intersection with a logical or
-    input
-        .lines()
-        .map(|line| {
-            let (_, line) = line.split_once(": ").unwrap();
-            let (wining, numbers) = line.split_once(" | ").unwrap();
-            let wining = wining
-                .split(' ')
-                .filter(|n| !n.is_empty())
-                .map(|n| 1 << n.parse::<u8>().unwrap())
-                .reduce(|acc: u128, e| acc | e)
-                .unwrap();
-            let numbers = numbers
-                .split(' ')
-                .filter(|n| !n.is_empty())
-                .map(|n| 1 << n.parse::<u8>().unwrap())
-                .reduce(|acc: u128, e| acc | e)
-                .unwrap();
-            let r: u128 = wining & numbers;
-            r.count_ones()
-        })
-        .collect()
+impl FromIterator<u32> for NumberSet {
+    fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> Self {
+        let mut numbers = iter.into_iter().collect::<Vec<_>>();
+        if numbers.iter().all(|n| *n < 128) {
+            return NumberSet::Bits(numbers.iter().fold(0, |acc, n| acc | 1 << n));
+        }
+        numbers.sort_unstable();
+        numbers.dedup();
+        NumberSet::Sorted(numbers)
+    }
 }
 
-pub fn part1(input: &str) -> usize {
-    generator_bitset(input)
-        .iter()
-        .map(|i| {
-            if *i == 0 {
-                return 0;
-            }
-            1 << (*i - 1)
-        })
-        .sum()
+impl NumberSet {
+    /// Numbers in increasing order
+    pub fn to_vec(&self) -> Vec<u32> {
+        match self {
+            NumberSet::Bits(bits) => (0..128).filter(|n| bits >> n & 1 == 1).collect(),
+            NumberSet::Sorted(numbers) => numbers.clone(),
+        }
+    }
+
+    pub fn count_common(&self, other: &NumberSet) -> u32 {
+        match (self, other) {
+            (NumberSet::Bits(a), NumberSet::Bits(b)) => (a & b).count_ones(),
+            (NumberSet::Bits(_), NumberSet::Sorted(b)) => merge_count(&self.to_vec(), b),
+            (NumberSet::Sorted(a), NumberSet::Bits(_)) => merge_count(a, &other.to_vec()),
+            (NumberSet::Sorted(a), NumberSet::Sorted(b)) => merge_count(a, b),
+        }
+    }
 }
 
-pub fn part2(input: &str) -> usize {
-    let input = generator_bitset(input);
-    let mut weight_vector = vec![1; input.len()];
-    for (i, n) in input.iter().enumerate() {
-        for j in 0..*n as usize {
-            if i + j + 1 < weight_vector.len() {
-                weight_vector[i + j + 1] += weight_vector[i];
+fn merge_count(a: &[u32], b: &[u32]) -> u32 {
+    let (mut i, mut j, mut count) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                count += 1;
+                i += 1;
+                j += 1;
             }
         }
     }
-    weight_vector.iter().sum()
+    count
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Card {
+    pub winning: NumberSet,
+    pub numbers: NumberSet,
 }
 
-pub fn generator_set(input: &str) -> Data {
+impl Card {
+    pub fn matches(&self) -> u32 {
+        self.winning.count_common(&self.numbers)
+    }
+}
+
+/// Cards of the input with the number of matches of each card, and the number of copies of
+/// each card we end up with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scratchcards {
+    pub cards: Vec<Card>,
+    pub matches: Vec<u32>,
+    pub copies: Vec<u64>,
+}
+
+pub fn parse_cards(input: &str) -> Vec<Card> {
+    let parse = |numbers: &str| {
+        numbers
+            .split_ascii_whitespace()
+            .map(|n| n.parse().unwrap())
+            .collect()
+    };
     input
         .lines()
         .map(|line| {
             let (_, line) = line.split_once(": ").unwrap();
-            let (wining, numbers) = line.split_once(" | ").unwrap();
-            let wining = wining
-                .split(' ')
-                .filter(|n| !n.is_empty())
-                .map(|n| n.parse().unwrap())
-                .collect();
-            let numbers = numbers
-                .split(' ')
-                .filter(|n| !n.is_empty())
-                .map(|n| n.parse().unwrap())
-                .collect();
-            (wining, numbers)
+            let (winning, numbers) = line.split_once(" | ").unwrap();
+            Card {
+                winning: parse(winning),
+                numbers: parse(numbers),
+            }
         })
         .collect()
 }
 
-pub fn part1_set(input: &str) -> usize {
-    generator_set(input)
+/// Each card wins one copy of the next `matches` cards, never past the last card
+pub fn copy_counts(matches: &[u32]) -> Vec<u64> {
+    let mut copies = vec![1; matches.len()];
+    for (i, n) in matches.iter().enumerate() {
+        let end = (i + 1 + *n as usize).min(copies.len());
+        for j in i + 1..end {
+            copies[j] += copies[i];
+        }
+    }
+    copies
+}
+
+pub fn generator(input: &[u8]) -> Scratchcards {
+    let cards = parse_cards(from_utf8(input).unwrap());
+    let matches = cards.iter().map(Card::matches).collect::<Vec<_>>();
+    let copies = copy_counts(&matches);
+    Scratchcards {
+        cards,
+        matches,
+        copies,
+    }
+}
+
+/// Points of a card, doubling for each match after the first
+fn score(matches: u32) -> u64 {
+    match matches {
+        0 => 0,
+        n => 1u64
+            .checked_shl(n - 1)
+            .expect("card score too large for u64"),
+    }
+}
+
+pub fn part1(input: &Scratchcards) -> u64 {
+    input.matches.iter().map(|n| score(*n)).sum()
+}
+
+pub fn part2(input: &Scratchcards) -> u64 {
+    input.copies.iter().sum()
+}
+
+fn to_sets(cards: &[Card]) -> Vec<(AHashSet<u32>, AHashSet<u32>)> {
+    cards
         .iter()
-        .map(|(w, n)| {
-            let i = w.intersection(n).count();
-            if i == 0 {
-                return 0;
-            }
-            1 << (i - 1)
+        .map(|card| {
+            (
+                card.winning.to_vec().into_iter().collect(),
+                card.numbers.to_vec().into_iter().collect(),
+            )
         })
+        .collect()
+}
+
+pub fn part1_set(input: &Scratchcards) -> u64 {
+    to_sets(&input.cards)
+        .iter()
+        .map(|(w, n)| score(w.intersection(n).count() as u32))
         .sum()
 }
 
-pub fn part2_set(input: &str) -> usize {
-    let input = generator_set(input);
+pub fn part2_set(input: &Scratchcards) -> u64 {
+    let input = to_sets(&input.cards);
     let mut weight_vector = vec![1; input.len()];
     for (i, (w, n)) in input.iter().enumerate() {
         for j in 0..w.intersection(n).count() {
@@ -123,7 +187,49 @@ mod tests {
             Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36\n\
             Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11\n"
             .as_bytes();
-        assert_eq!(part1(generator(example)), 13);
-        assert_eq!(part2(generator(example)), 30);
+        assert_eq!(part1(&generator(example)), 13);
+        assert_eq!(part2(&generator(example)), 30);
+
+        let cards = generator(example);
+        assert_eq!(cards.matches, vec![4, 2, 2, 1, 0, 0]);
+        assert_eq!(cards.copies, vec![1, 2, 4, 8, 14, 1]);
+    }
+
+    #[test]
+    fn test_large_numbers() {
+        let example = "Card 1: 128 3 1000 | 1000 3 7 129 128\n\
+            Card 2: 1 2 3 | 3 2 300\n\
+            Card 3: 5 127 | 127 5 5\n\
+            Card 4: 200 | 200\n"
+            .as_bytes();
+        let cards = generator(example);
+        assert_eq!(cards.cards[1].winning, NumberSet::Bits(0b1110));
+        assert_eq!(cards.cards[1].numbers, NumberSet::Sorted(vec![2, 3, 300]));
+        assert_eq!(cards.matches, vec![3, 2, 2, 1]);
+        assert_eq!(cards.copies, vec![1, 2, 4, 8]);
+        assert_eq!(part1(&cards), 4 + 2 + 2 + 1);
+        assert_eq!(part1_set(&cards), part1(&cards));
+        assert_eq!(part2_set(&cards), part2(&cards));
+    }
+
+    /// A card whose winning numbers are `0..n` and that holds all of them
+    fn matching_card(n: u32) -> String {
+        let numbers = (0..n).map(|v| v.to_string()).collect::<Vec<_>>().join(" ");
+        format!("Card 1: {numbers} | {numbers}\n")
+    }
+
+    #[test]
+    fn test_many_matches() {
+        let cards = generator(matching_card(64).as_bytes());
+        assert_eq!(part1(&cards), 1 << 63);
+        assert_eq!(part1_set(&cards), 1 << 63);
+    }
+
+    #[test]
+    #[should_panic(expected = "card score too large")]
+    fn test_score_overflow() {
+        let cards = generator(matching_card(65).as_bytes());
+        assert_eq!(cards.matches, vec![65]);
+        part1(&cards);
     }
 }
@@ -1,5 +1,7 @@
 use std::str::from_utf8;
 
+use crate::helper::intervals::{RangeMap, RangeSet};
+
 type Int = u64;
 
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
@@ -105,6 +107,37 @@ pub fn part2(input: &Input) -> Int {
     min
 }
 
+/// All the maps composed into a single function from seed to location
+pub fn seed_to_location(maps: &[Vec<Map>]) -> RangeMap<Int> {
+    maps.iter()
+        .map(|map| {
+            map.iter()
+                .map(|range| (range.source..range.source + range.len, range.dest))
+                .collect::<RangeMap<Int>>()
+        })
+        .fold(RangeMap::new(), |acc, map| acc.compose(&map))
+}
+
+/// Seed ranges of part2
+pub fn seed_ranges(seeds: &[Int]) -> RangeSet<Int> {
+    seeds.chunks(2).map(|s| s[0]..s[0] + s[1]).collect()
+}
+
+/// Locations of all the seeds of the set
+pub fn locations(maps: &[Vec<Map>], seeds: &RangeSet<Int>) -> RangeSet<Int> {
+    seed_to_location(maps).apply(seeds)
+}
+
+/// Seeds ending up in one of the locations of the set
+pub fn seeds_for_locations(maps: &[Vec<Map>], locations: &RangeSet<Int>) -> RangeSet<Int> {
+    seed_to_location(maps).preimage(locations)
+}
+
+pub fn part2_compose(input: &Input) -> Int {
+    let (seeds, maps) = input;
+    locations(maps, &seed_ranges(seeds)).min().unwrap()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -150,5 +183,33 @@ mod tests {
             .as_bytes();
         assert_eq!(part1(&generator(example)), 35);
         assert_eq!(part2(&generator(example)), 46);
+        assert_eq!(part2_compose(&generator(example)), 46);
+
+        let (seeds, maps) = generator(example);
+        let function = seed_to_location(&maps);
+        assert_eq!(seeds.iter().map(|s| function.get(*s)).min(), Some(35));
+        let locations = locations(&maps, &seed_ranges(&seeds));
+        assert_eq!(locations.len(), 14 + 13);
+        assert_eq!(locations.min(), Some(46));
+
+        // seed 82 is the only one going to location 46
+        let lowest = RangeSet::from_iter(Some(46..47));
+        let seeds_46 = seeds_for_locations(&maps, &lowest);
+        assert!(seeds_46.contains(82));
+        let seeds_46 = seeds_46.intersection(&seed_ranges(&seeds));
+        assert_eq!((seeds_46.min(), seeds_46.len()), (Some(82), 1));
+    }
+
+    #[test]
+    fn test_huge_ranges() {
+        let input = generator(include_bytes!("../inputs/day_5.txt"));
+        assert_eq!(part2_compose(&input), 10834440);
+        let (_, maps) = input;
+        // the whole domain in one range
+        let everything = RangeSet::from_iter(Some(0..Int::MAX / 2));
+        let locations = locations(&maps, &everything);
+        assert_eq!(locations.len(), Int::MAX / 2);
+        let all_seeds = seeds_for_locations(&maps, &locations);
+        assert!(everything.difference(&all_seeds).is_empty());
     }
 }
@@ -1,45 +1,81 @@
 use std::str::from_utf8;
 
+type Int = u128;
+
 pub fn generator(input: &[u8]) -> &str {
     let input = from_utf8(input).unwrap();
     input
 }
 
-pub fn part1(input: &str) -> usize {
-    let mut input = input.lines().map(|line| {
-        line.split_whitespace()
-            .skip(1)
-            .map(|v| v.parse::<f64>().unwrap())
-    });
+pub fn part1(input: &str) -> Int {
+    let mut input = input
+        .lines()
+        .map(|line| line.split_whitespace().skip(1).map(|v| v.parse().unwrap()));
     input
         .next()
         .unwrap()
         .zip(input.next().unwrap())
-        .map(|(t, d)| extract_roots(t, d))
+        .map(|(t, d)| ways_to_win(t, d))
         .product()
 }
 
-pub fn part2(input: &str) -> usize {
-    let input = input
-        .lines()
-        .map(|line| {
-            line.split_whitespace()
-                .skip(1)
-                .fold("".to_string(), |a, b| a + b)
-                .parse()
-                .unwrap()
+/// Read the digits of the line as a single number, ignoring the spaces between them
+fn concatenated(line: &str) -> Int {
+    line.bytes()
+        .filter(|b| b.is_ascii_digit())
+        .fold(0, |acc: Int, b| {
+            acc.checked_mul(10)
+                .and_then(|acc| acc.checked_add((b - b'0') as Int))
+                .expect("number too large")
         })
-        .collect::<Vec<f64>>();
-    let t = input[0];
-    let d = input[1];
-    extract_roots(t, d)
 }
 
-fn extract_roots(t: f64, d: f64) -> usize {
-    let sqrt_delta: f64 = (t * t - 4. * d).sqrt();
-    let s1 = (t + sqrt_delta) / 2. - 1e-5;
-    let s2 = (t - sqrt_delta) / 2. + 1e-5;
-    (s1.floor() - s2.ceil()) as usize + 1
+pub fn part2(input: &str) -> Int {
+    let mut input = input.lines().map(concatenated);
+    let t = input.next().unwrap();
+    let d = input.next().unwrap();
+    ways_to_win(t, d)
+}
+
+/// Floor of the square root of `n`
+pub fn isqrt(n: u128) -> u128 {
+    // the float estimate is only close to the root, one Newton step brings it within 1 of it
+    // and the exact checks fix the last unit
+    let mut r = (n as f64).sqrt() as u128;
+    if r > 0 {
+        r = (r + n / r) / 2;
+    }
+    while r.checked_mul(r).is_none_or(|sq| sq > n) {
+        r -= 1;
+    }
+    while (r + 1).checked_mul(r + 1).is_some_and(|sq| sq <= n) {
+        r += 1;
+    }
+    r
+}
+
+/// Shortest hold time going further than `distance`, None if the record can't be beaten.
+/// Panics if `time` doesn't fit in 64 bits or if `distance` doesn't fit in 126 bits.
+pub fn first_winning_hold(time: Int, distance: Int) -> Option<Int> {
+    let (t, d) = (time, distance);
+    let square = t.checked_mul(t).expect("time too large");
+    let d4 = d.checked_mul(4).expect("distance too large");
+    // h * (t - h) > d  <=>  (t - sqrt(delta)) / 2 < h < (t + sqrt(delta)) / 2
+    let delta = square.checked_sub(d4)?;
+    // rounding the root down can only give a hold time below the first winning one
+    let mut h = (t - isqrt(delta)) / 2;
+    while h * (t - h) <= d {
+        h += 1;
+        // the winning holds are symmetric around t / 2
+        if h > t / 2 {
+            return None;
+        }
+    }
+    Some(h)
+}
+
+pub fn ways_to_win(time: Int, distance: Int) -> Int {
+    first_winning_hold(time, distance).map_or(0, |h| time - 2 * h + 1)
 }
 
 #[cfg(test)]
@@ -58,4 +94,63 @@ mod tests {
         assert_eq!(part1(generator(example)), 288);
         assert_eq!(part2(generator(example)), 71503);
     }
+
+    #[test]
+    fn test_isqrt() {
+        for n in 0..10_000u128 {
+            let r = isqrt(n);
+            assert!(r * r <= n && n < (r + 1) * (r + 1));
+        }
+        for r in [1u128 << 53, (1 << 53) + 1, 1 << 63, u64::MAX as u128] {
+            assert_eq!(isqrt(r * r), r);
+            assert_eq!(isqrt(r * r - 1), r - 1);
+            assert_eq!(isqrt(r * r + 1), r);
+        }
+        assert_eq!(isqrt(u128::MAX), u64::MAX as u128);
+    }
+
+    #[test]
+    fn test_boundaries() {
+        let brute_force = |t: Int, d: Int| (0..=t).filter(|h| h * (t - h) > d).count() as Int;
+        for t in 0..60 {
+            for d in 0..t * t / 4 + 2 {
+                assert_eq!(ways_to_win(t, d), brute_force(t, d), "{t} {d}");
+            }
+        }
+
+        // roots exactly on integers: holding 10 or 20 ms only ties the record
+        assert_eq!(ways_to_win(30, 200), 9);
+        // a single root at t / 2, which ties the record
+        assert_eq!(ways_to_win(4, 4), 0);
+        // the two roots are 1 and 2
+        assert_eq!(ways_to_win(3, 2), 0);
+        assert_eq!(ways_to_win(3, 1), 2);
+
+        // above 2^53, where f64 can't tell the numbers apart
+        let t = (1 << 54) + 2;
+        let h = 1 << 27;
+        let d = h * (t - h);
+        assert_eq!(first_winning_hold(t, d), Some(h + 1));
+        assert_eq!(first_winning_hold(t, d - 1), Some(h));
+        assert_eq!(ways_to_win(t, d), t - 2 * h - 1);
+
+        let t = u64::MAX as Int;
+        assert_eq!(ways_to_win(t, 0), t - 1);
+        let half = t / 2;
+        assert_eq!(ways_to_win(t, half * (half + 1)), 0);
+        assert_eq!(ways_to_win(t, half * (half + 1) - 1), 2);
+        for h in [1 << 40, (1 << 62) + 3, half - 5] {
+            for d in [h * (t - h) - 1, h * (t - h), h * (t - h) + 1] {
+                let first = first_winning_hold(t, d).unwrap();
+                assert!(first * (t - first) > d);
+                assert!((first - 1) * (t - first + 1) <= d);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_overflow() {
+        ways_to_win(1 << 64, 0);
+    }
 }
@@ -1,55 +1,166 @@
-use std::str::from_utf8;
+use std::{fmt::Display, str::from_utf8};
 
-use arrayvec::ArrayVec;
-use itertools::Itertools;
+/// Hand categories, from the weakest to the strongest. Hands that don't have 5 cards are named
+/// after their two largest groups of cards.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Hash)]
+pub enum Category {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    FullHouse,
+    FourOfAKind,
+    FiveOfAKind,
+}
+
+impl Category {
+    /// `shape` is the size of each group of identical cards, in decreasing order
+    fn from_shape(shape: &[u8]) -> Self {
+        let first = shape.first().copied().unwrap_or_default();
+        let second = shape.get(1).copied().unwrap_or_default();
+        match (first, second) {
+            (5.., _) => Category::FiveOfAKind,
+            (4, _) => Category::FourOfAKind,
+            (3, 2..) => Category::FullHouse,
+            (3, _) => Category::ThreeOfAKind,
+            (2, 2) => Category::TwoPair,
+            (2, _) => Category::OnePair,
+            _ => Category::HighCard,
+        }
+    }
+}
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
-pub struct Hand {
-    strength: u8,
-    cards: ArrayVec<u8, 5>,
-    bid: u32,
+pub struct Hand<'a> {
+    // hands are compared on their shape first, then on the rank of their cards, in order
+    shape: Vec<u8>,
+    ranks: Vec<u8>,
+    pub cards: &'a str,
+    pub category: Category,
+    pub bid: u32,
 }
 
-fn compute_strength1(cards: &[u8]) -> u8 {
-    let c = cards.iter().counts();
-    let l = c.len();
-    let m = c.values().max().unwrap().to_owned();
-
-    match (l, m) {
-        (1, 5) => 7,
-        (2, 4) => 6,
-        (2, 3) => 5,
-        (3, 3) => 4,
-        (3, 2) => 3,
-        (4, 2) => 2,
-        (5, 1) => 1,
-        _ => unreachable!(),
+impl<'a> Display for Hand<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {:?} (groups {:?}, ranks {:?}) bid {}",
+            self.cards, self.category, self.shape, self.ranks, self.bid
+        )
     }
 }
 
-fn compute_strength2(cards: &[u8]) -> u8 {
-    let mut c = cards.iter().counts();
-    if c.len() == 1 {
-        return 7;
+/// Rules of a Camel Cards game
+#[derive(Clone, Debug)]
+pub struct Ruleset {
+    // 1 + rank of each card, 0 for the cards not in the game
+    ranks: [u8; 256],
+    wildcards: [bool; 256],
+    hand_size: usize,
+}
+
+impl Ruleset {
+    /// `order` lists the cards from the weakest to the strongest. The `wildcards` act as
+    /// whatever card makes the hand strongest, but keep their own rank to break ties.
+    pub fn new(order: &str, wildcards: &str, hand_size: usize) -> Self {
+        assert!(order.len() < 256, "too many cards");
+        let mut ranks = [0; 256];
+        for (i, card) in order.bytes().enumerate() {
+            ranks[card as usize] = i as u8 + 1;
+        }
+        let mut wild = [false; 256];
+        for card in wildcards.bytes() {
+            assert!(
+                ranks[card as usize] != 0,
+                "wildcard {:?} isn't a card",
+                card as char
+            );
+            wild[card as usize] = true;
+        }
+        Self {
+            ranks,
+            wildcards: wild,
+            hand_size,
+        }
+    }
+
+    /// Rules of part1
+    pub fn standard() -> Self {
+        Self::new("23456789TJQKA", "", 5)
+    }
+
+    /// Rules of part2, where J are jokers
+    pub fn jokers() -> Self {
+        Self::new("J23456789TQKA", "J", 5)
     }
 
-    // handle jokers
-    let n_j = c.remove(&0).unwrap_or_default();
-    let (k, v) = c.iter().max_by(|x, y| x.1.cmp(y.1)).unwrap();
-    c.insert(k, v + n_j);
-
-    let l = c.len();
-    let m = c.values().max().unwrap().to_owned();
-
-    match (l, m) {
-        (1, 5) => 7,
-        (2, 4) => 6,
-        (2, 3) => 5,
-        (3, 3) => 4,
-        (3, 2) => 3,
-        (4, 2) => 2,
-        (5, 1) => 1,
-        _ => unreachable!(),
+    fn rank(&self, card: u8) -> u8 {
+        let rank = self.ranks[card as usize];
+        assert!(rank != 0, "unknown card {:?}", card as char);
+        rank - 1
+    }
+
+    /// Size of each group of identical cards in decreasing order, wildcards join the largest
+    fn shape(&self, cards: &str) -> Vec<u8> {
+        let mut counts = [0u8; 256];
+        let mut n_wild = 0;
+        for card in cards.bytes() {
+            if self.wildcards[card as usize] {
+                n_wild += 1;
+            } else {
+                counts[card as usize] += 1;
+            }
+        }
+        let mut shape = counts.into_iter().filter(|c| *c != 0).collect::<Vec<_>>();
+        shape.sort_unstable_by(|a, b| b.cmp(a));
+        match shape.first_mut() {
+            Some(largest) => *largest += n_wild,
+            None => shape.push(n_wild),
+        }
+        shape
+    }
+
+    pub fn category(&self, cards: &str) -> Category {
+        Category::from_shape(&self.shape(cards))
+    }
+
+    pub fn hand<'a>(&self, cards: &'a str, bid: u32) -> Hand<'a> {
+        assert_eq!(
+            cards.len(),
+            self.hand_size,
+            "{cards} doesn't have {} cards",
+            self.hand_size
+        );
+        let ranks = cards.bytes().map(|card| self.rank(card)).collect();
+        let shape = self.shape(cards);
+        Hand {
+            category: Category::from_shape(&shape),
+            shape,
+            ranks,
+            cards,
+            bid,
+        }
+    }
+
+    /// Hands of the input from the weakest to the strongest, the rank of a hand is its index + 1
+    pub fn ranked_hands<'a>(&self, input: &'a str) -> Vec<Hand<'a>> {
+        let mut hands = input
+            .lines()
+            .map(|l| {
+                let (cards, bid) = l.split_once(' ').unwrap();
+                self.hand(cards, bid.parse().unwrap())
+            })
+            .collect::<Vec<_>>();
+        hands.sort_unstable();
+        hands
+    }
+
+    pub fn winnings(&self, input: &str) -> u64 {
+        self.ranked_hands(input)
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| (i as u64 + 1) * v.bid as u64)
+            .sum()
     }
 }
 
@@ -59,67 +170,11 @@ pub fn generator(input: &[u8]) -> &str {
 }
 
 pub fn part1(input: &str) -> u64 {
-    let mut v = input
-        .lines()
-        .map(|l| {
-            let (cards, bid) = l.split_once(' ').unwrap();
-            let bid = bid.parse().unwrap();
-            let cards = cards
-                .bytes()
-                .map(|v| match v {
-                    b'A' => 15,
-                    b'K' => 14,
-                    b'Q' => 13,
-                    b'J' => 12,
-                    b'T' => 11,
-                    v => v - 48,
-                })
-                .collect::<ArrayVec<_, 5>>();
-            let strength = compute_strength1(&cards);
-            Hand {
-                cards,
-                strength,
-                bid,
-            }
-        })
-        .collect::<ArrayVec<Hand, 1000>>();
-    v.sort_unstable();
-    v.into_iter()
-        .enumerate()
-        .map(|(i, v)| (i as u64 + 1) * v.bid as u64)
-        .sum()
+    Ruleset::standard().winnings(input)
 }
 
 pub fn part2(input: &str) -> u64 {
-    let mut v = input
-        .lines()
-        .map(|l| {
-            let (cards, bid) = l.split_once(' ').unwrap();
-            let bid = bid.parse().unwrap();
-            let cards = cards
-                .bytes()
-                .map(|v| match v {
-                    b'A' => 15,
-                    b'K' => 14,
-                    b'Q' => 13,
-                    b'J' => 0,
-                    b'T' => 11,
-                    v => v - 48,
-                })
-                .collect::<ArrayVec<_, 5>>();
-            let strength = compute_strength2(&cards);
-            Hand {
-                cards,
-                strength,
-                bid,
-            }
-        })
-        .collect::<ArrayVec<Hand, 1000>>();
-    v.sort_unstable();
-    v.into_iter()
-        .enumerate()
-        .map(|(i, v)| (i as u64 + 1) * v.bid as u64)
-        .sum()
+    Ruleset::jokers().winnings(input)
 }
 
 #[cfg(test)]
@@ -140,4 +195,60 @@ mod tests {
         assert_eq!(part1(generator(example)), 6440);
         assert_eq!(part2(generator(example)), 5905);
     }
+
+    #[test]
+    fn test_ruleset() {
+        let example = "32T3K 765\n\
+                        T55J5 684\n\
+                        KK677 28\n\
+                        KTJJT 220\n\
+                        QQQJA 483";
+        let hands = Ruleset::jokers().ranked_hands(example);
+        let categories = hands
+            .iter()
+            .map(|h| (h.cards, h.category))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            categories,
+            vec![
+                ("32T3K", Category::OnePair),
+                ("KK677", Category::TwoPair),
+                ("T55J5", Category::FourOfAKind),
+                ("QQQJA", Category::FourOfAKind),
+                ("KTJJT", Category::FourOfAKind),
+            ]
+        );
+        assert_eq!(
+            hands[0].to_string(),
+            "32T3K OnePair (groups [2, 1, 1, 1], ranks [2, 1, 9, 2, 11]) bid 765"
+        );
+
+        let standard = Ruleset::standard();
+        assert_eq!(standard.category("KTJJT"), Category::TwoPair);
+        assert_eq!(standard.category("JJJJJ"), Category::FiveOfAKind);
+        assert_eq!(Ruleset::jokers().category("JJJJJ"), Category::FiveOfAKind);
+        assert_eq!(Ruleset::jokers().category("JJJJ2"), Category::FiveOfAKind);
+
+        // two kinds of wildcards, with 7 cards per hand
+        let rules = Ruleset::new("*23456789TJQKA?", "*?", 7);
+        assert_eq!(rules.category("2345678"), Category::HighCard);
+        assert_eq!(rules.category("2345*?A"), Category::ThreeOfAKind);
+        assert_eq!(rules.category("22334*?"), Category::FourOfAKind);
+        assert_eq!(rules.category("2233344"), Category::FullHouse);
+        let hands = rules.ranked_hands("2233344 1\n2222333 2\n22223*3 3\n*?*?*?A 4");
+        assert_eq!(
+            hands.iter().map(|h| h.bid).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4]
+        );
+        assert_eq!(
+            rules.winnings("2233344 1\n2222333 2\n22223*3 3\n*?*?*?A 4"),
+            30
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_wrong_size() {
+        Ruleset::standard().hand("AAAA", 1);
+    }
 }
@@ -1,14 +1,10 @@
 use ahash::HashSet;
-use arrayvec::ArrayVec;
-use nohash_hasher::{BuildNoHashHasher, NoHashHasher};
-use std::{collections::HashMap, hash::BuildHasherDefault, str::from_utf8};
+use std::str::from_utf8;
 
-use num::integer::lcm;
+use num::{integer::lcm, Integer};
 
-const MIN_ASCII: u8 = 48;
-const RANGE_ASCII: u32 = 90 - 48;
+use crate::helper::FixedInterner;
 
-type Map = HashMap<u32, u16, BuildHasherDefault<NoHashHasher<u32>>>;
 type Type = (Vec<u8>, Maps);
 
 type Int = u16;
@@ -21,8 +17,8 @@ struct Node {
 
 #[derive(Default, Debug)]
 pub struct Maps {
-    nodes: ArrayVec<Node, 766>,
-    beginning_part2: ArrayVec<Int, 10>,
+    nodes: Vec<Node>,
+    beginning_part2: Vec<Int>,
     end_part2: HashSet<Int>,
     beginning_part1: Int,
     end_part1: Int,
@@ -37,54 +33,43 @@ impl Maps {
         self.nodes[id as usize].right
     }
 
-    fn new_node(&mut self) -> Int {
-        let id = self.nodes.len() as Int;
-        self.nodes.push(Node::default());
-        id
+    fn next_node(&self, id: Int, direction: u8) -> Int {
+        if direction == b'R' {
+            self.right_node(id)
+        } else {
+            self.left_node(id)
+        }
     }
 }
 
-fn perfect_hash(text: &str) -> u32 {
-    text.bytes()
-        .map(|x| (x - MIN_ASCII) as u32)
-        .reduce(|acc, x| acc * RANGE_ASCII + x)
-        .unwrap()
-}
-
 pub fn generator(input: &[u8]) -> Type {
     let input = from_utf8(input).unwrap();
 
     let (directions, maps) = input.split_once("\n\n").unwrap();
 
-    let mut str_to_id: Map = HashMap::with_capacity_and_hasher(766, BuildNoHashHasher::default());
-    let mut all_maps = Maps::default();
-
-    str_to_id.insert(perfect_hash("AAA"), all_maps.new_node());
-    str_to_id.insert(perfect_hash("ZZZ"), all_maps.new_node());
+    let mut labels = FixedInterner::<3, Int>::new();
+    let mut all_maps = Maps {
+        beginning_part1: labels.intern(b"AAA"),
+        end_part1: labels.intern(b"ZZZ"),
+        ..Default::default()
+    };
 
     // TODO count the number of items with "**Z" and group them at the end of the maps, so we don't need to compute hashes anymore
     // TODO solution 2 : keep a marker in Node if this is an end node
-    all_maps.end_part1 = 1;
 
     for l in maps.lines() {
         let (map, links) = l.split_once(" = ").unwrap();
         let links = links[1..links.len() - 1].split_once(", ").unwrap();
 
-        let map_id = str_to_id
-            .entry(perfect_hash(map))
-            .or_insert_with(|| all_maps.new_node())
-            .to_owned();
-        let left_id = str_to_id
-            .entry(perfect_hash(links.0))
-            .or_insert_with(|| all_maps.new_node())
-            .to_owned();
-        let right_id = str_to_id
-            .entry(perfect_hash(links.1))
-            .or_insert_with(|| all_maps.new_node())
-            .to_owned();
+        let map_id = labels.intern(map.as_bytes());
+        let left_id = labels.intern(links.0.as_bytes());
+        let right_id = labels.intern(links.1.as_bytes());
+        while all_maps.nodes.len() < labels.len() {
+            all_maps.nodes.push(Node::default());
+        }
         all_maps.nodes[map_id as usize] = Node {
-            left: left_id.to_owned() as u16,
-            right: right_id.to_owned() as u16,
+            left: left_id,
+            right: right_id,
         };
 
         if map.ends_with('A') {
@@ -114,36 +99,154 @@ pub fn part1(input: &Type) -> usize {
     i
 }
 
-pub fn part2(input: &Type) -> usize {
+/// Steps at which a ghost is on a ..Z node. After `pre_period` steps the walk repeats every
+/// `cycle_length` steps, `pre_hits` are the steps before the cycle and `cycle_hits` the steps
+/// of the first cycle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GhostCycle {
+    pub pre_period: u64,
+    pub cycle_length: u64,
+    pub pre_hits: Vec<u64>,
+    pub cycle_hits: Vec<u64>,
+}
+
+impl GhostCycle {
+    pub fn is_hit(&self, step: u64) -> bool {
+        if step < self.pre_period {
+            return self.pre_hits.binary_search(&step).is_ok();
+        }
+        let step = self.pre_period + (step - self.pre_period) % self.cycle_length;
+        self.cycle_hits.binary_search(&step).is_ok()
+    }
+
+    /// The period if the ghost is on a ..Z node exactly at the multiples of the first step it
+    /// reaches one, as in the puzzle input
+    fn aligned_period(&self) -> Option<u64> {
+        let hits = self.pre_hits.iter().chain(self.cycle_hits.iter());
+        let period = *hits.clone().next()?;
+        let end = self.pre_period + self.cycle_length;
+        let aligned = period > 0
+            && Integer::is_multiple_of(&self.cycle_length, &period)
+            && hits.clone().count() as u64 == (end - 1) / period
+            && hits.enumerate().all(|(i, h)| *h == (i as u64 + 1) * period);
+        aligned.then_some(period)
+    }
+}
+
+pub fn ghost_cycle(input: &Type, start: Int) -> GhostCycle {
     let directions = &input.0;
     let maps = &input.1;
 
-    maps.beginning_part2
-        .iter()
-        .map(|v| compute_n(*v, maps, directions))
-        .reduce(lcm)
-        .unwrap()
+    // the walk repeats once a node is seen twice at the beginning of the directions
+    let mut seen = vec![u64::MAX; maps.nodes.len()];
+    let mut hits = vec![];
+    let mut node = start;
+    let mut step = 0;
+    let (pre_period, cycle_length) = loop {
+        let i = step as usize % directions.len();
+        if i == 0 {
+            let first = seen[node as usize];
+            if first != u64::MAX {
+                break (first, step - first);
+            }
+            seen[node as usize] = step;
+        }
+        if maps.end_part2.contains(&node) {
+            hits.push(step);
+        }
+        node = maps.next_node(node, directions[i]);
+        step += 1;
+    };
+    let cycle_hits = hits.split_off(hits.partition_point(|h| *h < pre_period));
+    GhostCycle {
+        pre_period,
+        cycle_length,
+        pre_hits: hits,
+        cycle_hits,
+    }
 }
 
-fn compute_n(current_map: Int, maps: &Maps, directions: &Vec<u8>) -> usize {
-    let mut i = 0;
-    let mut current_map = current_map;
-    while !maps.end_part2.contains(&current_map) {
-        if directions[i % directions.len()] == b'R' {
-            current_map = maps.right_node(current_map);
-        } else {
-            current_map = maps.left_node(current_map);
-        }
-        i += 1;
+/// Solve x = r1 mod m1 and x = r2 mod m2, for any moduli. The solution is modulo lcm(m1, m2).
+fn crt(r1: i128, m1: i128, r2: i128, m2: i128) -> Option<i128> {
+    let e = m1.extended_gcd(&m2);
+    if (r2 - r1) % e.gcd != 0 {
+        return None;
     }
-    i
+    // m1 * k = r2 - r1 mod m2, with e.x the inverse of m1 / gcd modulo m2 / gcd
+    let m = m2 / e.gcd;
+    let k = ((r2 - r1) / e.gcd % m * (e.x % m)).rem_euclid(m);
+    Some((r1 + m1 * k).rem_euclid(m1 / e.gcd * m2))
 }
+
+/// First step where all the ghosts are on a ..Z node at the same time, if any
+pub fn ghost_steps(input: &Type) -> Option<u64> {
+    let cycles = input
+        .1
+        .beginning_part2
+        .iter()
+        .map(|start| ghost_cycle(input, *start))
+        .collect::<Vec<_>>();
+
+    if let Some(periods) = cycles
+        .iter()
+        .map(GhostCycle::aligned_period)
+        .collect::<Option<Vec<_>>>()
+    {
+        return Some(periods.into_iter().fold(1, lcm));
+    }
+
+    // before the last pre-period ends, only its ghost's pre hits can work
+    let last = cycles.iter().max_by_key(|c| c.pre_period)?;
+    if let Some(step) = last
+        .pre_hits
+        .iter()
+        .find(|step| cycles.iter().all(|c| c.is_hit(**step)))
+    {
+        return Some(*step);
+    }
+
+    // after it every ghost is in its cycle
+    let start = last.pre_period as i128;
+    let mut residues = vec![0];
+    let mut modulus = 1;
+    for c in cycles.iter() {
+        let m = c.cycle_length as i128;
+        let mut next = residues
+            .iter()
+            .flat_map(|r| {
+                c.cycle_hits
+                    .iter()
+                    .filter_map(move |h| crt(*r, modulus, *h as i128 % m, m))
+            })
+            .collect::<Vec<_>>();
+        next.sort_unstable();
+        next.dedup();
+        residues = next;
+        modulus = lcm(modulus, m);
+    }
+    residues
+        .into_iter()
+        .map(|r| {
+            if r >= start {
+                r
+            } else {
+                r + Integer::div_ceil(&(start - r), &modulus) * modulus
+            }
+        })
+        .min()
+        .map(|step| step.try_into().expect("too many steps for u64"))
+}
+
+pub fn part2(input: &Type) -> usize {
+    ghost_steps(input).unwrap() as usize
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use aoc_macro::test_parts;
-    
+
     test_parts!(8, 20513, 15995167053923);
 
     #[test]
@@ -170,4 +273,63 @@ mod tests {
 
         assert_eq!(part2(&generator(example)), 6);
     }
+
+    // ghosts with a direction list of a single "L", and the steps they are on a ..Z node
+    // on 2, 5, 8...
+    const GHOST_1: &str = "11A = (11B, 11B)\n11B = (11Z, 11Z)\n11Z = (11C, 11C)\n11C = (11B, 11B)";
+    // on 1, 5, 9...
+    const GHOST_2: &str =
+        "22A = (22Z, 22Z)\n22Z = (22B, 22B)\n22B = (22C, 22C)\n22C = (22D, 22D)\n22D = (22Z, 22Z)";
+    // on 3, 9, 15...
+    const GHOST_3: &str = "33A = (33B, 33B)\n33B = (33C, 33C)\n33C = (33Z, 33Z)\n\
+        33Z = (33D, 33D)\n33D = (33E, 33E)\n33E = (33F, 33F)\n33F = (33G, 33G)\n\
+        33G = (33H, 33H)\n33H = (33Z, 33Z)";
+    // on 1 only
+    const GHOST_4: &str = "44A = (44Z, 44Z)\n44Z = (44B, 44B)\n44B = (44B, 44B)";
+    // on 2, 3, 6, 7, 10, 11...
+    const GHOST_5: &str =
+        "55A = (55B, 55B)\n55B = (Y5Z, Y5Z)\nY5Z = (55Z, 55Z)\n55Z = (55C, 55C)\n55C = (55B, 55B)";
+
+    fn ghosts(ghosts: &[&str]) -> Option<u64> {
+        let input = format!("L\n\n{}", ghosts.join("\n"));
+        ghost_steps(&generator(input.as_bytes()))
+    }
+
+    #[test]
+    fn test_ghost_cycle() {
+        let input = format!("L\n\n{GHOST_1}");
+        let input = generator(input.as_bytes());
+        assert_eq!(
+            ghost_cycle(&input, input.1.beginning_part2[0]),
+            GhostCycle {
+                pre_period: 1,
+                cycle_length: 3,
+                pre_hits: vec![],
+                cycle_hits: vec![2],
+            }
+        );
+
+        let input = generator(include_bytes!("../inputs/day_8.txt"));
+        for start in input.1.beginning_part2.iter() {
+            let cycle = ghost_cycle(&input, *start);
+            assert!(cycle.aligned_period().is_some());
+            assert!(cycle.is_hit(cycle.cycle_hits[0] * 3));
+        }
+    }
+
+    #[test]
+    fn test_general_ghosts() {
+        // the lcm of the first steps on a ..Z node would give 2
+        assert_eq!(ghosts(&[GHOST_1, GHOST_2]), Some(5));
+        // non coprime cycle lengths 4 and 6
+        assert_eq!(ghosts(&[GHOST_2, GHOST_3]), Some(9));
+        assert_eq!(ghosts(&[GHOST_1, GHOST_3]), None);
+        // a hit before the cycle
+        assert_eq!(ghosts(&[GHOST_2, GHOST_4]), Some(1));
+        assert_eq!(ghosts(&[GHOST_1, GHOST_4]), None);
+        // several hits per cycle
+        assert_eq!(ghosts(&[GHOST_5, GHOST_1]), Some(2));
+        assert_eq!(ghosts(&[GHOST_5, GHOST_3]), Some(3));
+        assert_eq!(ghosts(&[GHOST_5, GHOST_2]), None);
+    }
 }
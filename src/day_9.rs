@@ -1,10 +1,8 @@
-use std::str::from_utf8;
-
-use arrayvec::ArrayVec;
+use std::{fmt::Display, str::from_utf8};
 
 type Int = i64;
-type Line = ArrayVec<Int, 21>;
-type Type = ArrayVec<Line, 200>;
+type Line = Vec<Int>;
+type Type = Vec<Line>;
 
 pub fn generator(input: &[u8]) -> Type {
     let input = from_utf8(input).unwrap();
@@ -65,6 +63,108 @@ fn get_previous(seq: &Line) -> Int {
         seq[0] - get_previous(&diff)
     }
 }
+
+/// The sequence doesn't reach a row of zero differences before running out of values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotPolynomial;
+
+impl Display for NotPolynomial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "sequence is not polynomial within its length")
+    }
+}
+
+impl std::error::Error for NotPolynomial {}
+
+/// Polynomial of minimal degree going through a sequence, in Newton form: the value at
+/// position x is the sum of `coefficients[j] * binomial(x, j)`, where position 0 is the first
+/// value of the sequence. Everything is exact, an overflow panics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polynomial {
+    coefficients: Vec<i128>,
+    len: usize,
+}
+
+impl Polynomial {
+    /// Fit the sequence, which needs at least one more value than a degree `d` polynomial
+    /// requires so that the last row of differences can be checked to be zero
+    pub fn fit(seq: &[Int]) -> Result<Self, NotPolynomial> {
+        let mut coefficients = vec![];
+        let mut diff = seq.iter().map(|v| *v as i128).collect::<Vec<_>>();
+        while diff.iter().any(|v| *v != 0) {
+            if diff.len() == 1 {
+                return Err(NotPolynomial);
+            }
+            coefficients.push(diff[0]);
+            diff = diff
+                .windows(2)
+                .map(|s| s[1].checked_sub(s[0]).expect("overflow in differences"))
+                .collect();
+        }
+        if diff.is_empty() {
+            return Err(NotPolynomial);
+        }
+        Ok(Self {
+            coefficients,
+            len: seq.len(),
+        })
+    }
+
+    /// Degree of the polynomial, 0 for the constant 0 too
+    pub fn degree(&self) -> usize {
+        self.coefficients.len().saturating_sub(1)
+    }
+
+    pub fn value_at(&self, x: i128) -> i128 {
+        let mut binomial = 1i128;
+        let mut value = 0i128;
+        for (j, c) in self.coefficients.iter().enumerate() {
+            if j > 0 {
+                // binomial(x, j) = binomial(x, j - 1) * (x - j + 1) / j, always exact
+                binomial = binomial
+                    .checked_mul(x - j as i128 + 1)
+                    .expect("overflow in binomial")
+                    / j as i128;
+            }
+            value = c
+                .checked_mul(binomial)
+                .and_then(|term| value.checked_add(term))
+                .expect("overflow in polynomial value");
+        }
+        value
+    }
+
+    /// Value `k` steps after the last one of the sequence
+    pub fn after(&self, k: u64) -> i128 {
+        self.value_at(self.len as i128 - 1 + k as i128)
+    }
+
+    /// Value `k` steps before the first one of the sequence
+    pub fn before(&self, k: u64) -> i128 {
+        self.value_at(-(k as i128))
+    }
+}
+
+pub fn part1_polynomial(input: &Type) -> Int {
+    input
+        .iter()
+        .map(|seq| {
+            let value = Polynomial::fit(seq).unwrap().after(1);
+            Int::try_from(value).expect("value too large")
+        })
+        .sum()
+}
+
+pub fn part2_polynomial(input: &Type) -> Int {
+    input
+        .iter()
+        .map(|seq| {
+            let value = Polynomial::fit(seq).unwrap().before(1);
+            Int::try_from(value).expect("value too large")
+        })
+        .sum()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -81,4 +181,38 @@ mod tests {
         assert_eq!(part1(&generator(example)), 114);
         assert_eq!(part2(&generator(example)), 2);
     }
+
+    #[test]
+    fn test_polynomial() {
+        let p = Polynomial::fit(&[10, 13, 16, 21, 30, 45]).unwrap();
+        assert_eq!(p.degree(), 3);
+        assert_eq!((p.before(1), p.after(1)), (5, 68));
+        // 10 + 3x + 2 * binomial(x, 3)
+        let expected = |x: i128| 10 + 3 * x + x * (x - 1) * (x - 2) / 3;
+        for x in -50..50 {
+            assert_eq!(p.value_at(x), expected(x));
+        }
+        assert_eq!(p.after(1_000_000), expected(1_000_005));
+        assert_eq!(p.before(1_000_000), expected(-1_000_000));
+
+        assert_eq!(Polynomial::fit(&[0, 0, 0]).unwrap().after(10), 0);
+        assert_eq!(Polynomial::fit(&[7, 7]).unwrap().before(3), 7);
+        // x^3 needs 5 values to be recognized
+        assert_eq!(Polynomial::fit(&[0, 1, 8, 27]), Err(NotPolynomial));
+        let cube = Polynomial::fit(&[-8, -1, 0, 1, 8]).unwrap();
+        assert_eq!((cube.degree(), cube.value_at(-10)), (3, -12 * 12 * 12));
+        assert_eq!(Polynomial::fit(&[1, 2, 4, 8, 16, 32]), Err(NotPolynomial));
+        assert_eq!(Polynomial::fit(&[5]), Err(NotPolynomial));
+        assert_eq!(Polynomial::fit(&[]), Err(NotPolynomial));
+
+        // every line of the input has enough values
+        let input = generator(include_bytes!("../inputs/day_9.txt"));
+        assert_eq!(part1_polynomial(&input), part1(&input));
+        assert_eq!(part2_polynomial(&input), part2(&input));
+        for seq in input.iter() {
+            let p = Polynomial::fit(seq).unwrap();
+            assert_eq!(p.before(0), seq[0] as i128);
+            assert_eq!(p.after(0), seq[seq.len() - 1] as i128);
+        }
+    }
 }
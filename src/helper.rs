@@ -1,3 +1,11 @@
+pub mod geometry;
+pub mod graph;
+pub mod intervals;
+pub mod search;
+
+mod interner;
+pub use interner::{FixedInterner, Interner};
+
 use arrayvec::ArrayVec;
 use std::{fmt::Debug, str::FromStr};
 
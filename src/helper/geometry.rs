@@ -0,0 +1,230 @@
+use std::cmp::Ordering;
+
+use num::{integer::gcd, BigInt, Signed, ToPrimitive, Zero};
+
+/// Position of a point relative to a polygon
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointLocation {
+    Inside,
+    Boundary,
+    Outside,
+}
+
+// Coordinates are converted to i128 and the computations are first tried with checked i128
+// arithmetic, falling back to big integers when it overflows. The results are exact and only
+// None when they don't fit in the returned type.
+fn wide<T: Copy + Into<i128>>(p: &(T, T)) -> (i128, i128) {
+    (p.0.into(), p.1.into())
+}
+
+fn cross(o: (i128, i128), a: (i128, i128), b: (i128, i128)) -> Option<i128> {
+    let (ax, ay) = (a.0.checked_sub(o.0)?, a.1.checked_sub(o.1)?);
+    let (bx, by) = (b.0.checked_sub(o.0)?, b.1.checked_sub(o.1)?);
+    ax.checked_mul(by)?.checked_sub(ay.checked_mul(bx)?)
+}
+
+fn cross_big(o: (i128, i128), a: (i128, i128), b: (i128, i128)) -> BigInt {
+    let big = |p: (i128, i128)| (BigInt::from(p.0), BigInt::from(p.1));
+    let (o, a, b) = (big(o), big(a), big(b));
+    (&a.0 - &o.0) * (&b.1 - &o.1) - (&a.1 - &o.1) * (&b.0 - &o.0)
+}
+
+fn edges<T: Copy + Into<i128>>(
+    vertices: &[(T, T)],
+) -> impl Iterator<Item = ((i128, i128), (i128, i128))> + '_ {
+    vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|(a, b)| (wide(a), wide(b)))
+}
+
+fn double_area_big<T: Copy + Into<i128>>(vertices: &[(T, T)]) -> BigInt {
+    let Some(origin) = vertices.first().map(wide) else {
+        return BigInt::zero();
+    };
+    // translating to the first vertex keeps the products small
+    let fast = edges(vertices).try_fold(0i128, |acc, (a, b)| acc.checked_add(cross(origin, a, b)?));
+    match fast {
+        Some(area) => BigInt::from(area),
+        None => edges(vertices).map(|(a, b)| cross_big(origin, a, b)).sum(),
+    }
+}
+
+fn boundary_points_big<T: Copy + Into<i128>>(vertices: &[(T, T)]) -> BigInt {
+    if vertices.len() < 2 {
+        return BigInt::from(vertices.len());
+    }
+    // the difference of two i128 always fits in a u128
+    let steps = || edges(vertices).map(|(a, b)| gcd(a.0.abs_diff(b.0), a.1.abs_diff(b.1)));
+    match steps().try_fold(0u128, |acc, n| acc.checked_add(n)) {
+        Some(count) => BigInt::from(count),
+        None => steps().map(BigInt::from).sum(),
+    }
+}
+
+/// Twice the signed area of the polygon (Shoelace formula), positive when the vertices are
+/// counter-clockwise in a y-up frame. The polygon is closed implicitly.
+pub fn signed_double_area<T: Copy + Into<i128>>(vertices: &[(T, T)]) -> Option<i128> {
+    double_area_big(vertices).to_i128()
+}
+
+/// Area of the polygon, rounded down when it is half-integral
+pub fn shoelace_area<T: Copy + Into<i128>>(vertices: &[(T, T)]) -> Option<u128> {
+    (double_area_big(vertices).abs() / 2u8).to_u128()
+}
+
+/// Number of lattice points on the edges of the polygon
+pub fn boundary_points<T: Copy + Into<i128>>(vertices: &[(T, T)]) -> Option<u128> {
+    boundary_points_big(vertices).to_u128()
+}
+
+/// Number of lattice points strictly inside a simple polygon, using Pick's theorem
+pub fn interior_points<T: Copy + Into<i128>>(vertices: &[(T, T)]) -> Option<u128> {
+    let double_area = double_area_big(vertices).abs();
+    if double_area.is_zero() {
+        return Some(0);
+    }
+    // A = i + b/2 - 1
+    ((double_area + 2u8 - boundary_points_big(vertices)) / 2u8).to_u128()
+}
+
+/// Locate a point relative to a simple polygon, with the even-odd rule
+pub fn locate_point<T: Copy + Into<i128>>(vertices: &[(T, T)], point: (T, T)) -> PointLocation {
+    let p = wide(&point);
+    let mut inside = false;
+    for (a, b) in edges(vertices) {
+        // only the sign of the cross product matters
+        let c =
+            cross(a, b, p).map_or_else(|| cross_big(a, b, p).cmp(&BigInt::zero()), |c| c.cmp(&0));
+        if c == Ordering::Equal
+            && a.0.min(b.0) <= p.0
+            && p.0 <= a.0.max(b.0)
+            && a.1.min(b.1) <= p.1
+            && p.1 <= a.1.max(b.1)
+        {
+            return PointLocation::Boundary;
+        }
+        // half-open rule on y so that a vertex is never counted twice
+        if (a.1 > p.1) != (b.1 > p.1) && (c == Ordering::Greater) == (b.1 > a.1) {
+            inside = !inside;
+        }
+    }
+    if inside {
+        PointLocation::Inside
+    } else {
+        PointLocation::Outside
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base() {
+        // 4x3 rectangle
+        let rectangle = [(0i64, 0i64), (4, 0), (4, 3), (0, 3)];
+        assert_eq!(signed_double_area(&rectangle), Some(24));
+        assert_eq!(shoelace_area(&rectangle), Some(12));
+        assert_eq!(boundary_points(&rectangle), Some(14));
+        assert_eq!(interior_points(&rectangle), Some(3 * 2));
+
+        let clockwise = rectangle.iter().rev().copied().collect::<Vec<_>>();
+        assert_eq!(signed_double_area(&clockwise), Some(-24));
+        assert_eq!(interior_points(&clockwise), Some(6));
+
+        // triangle with a half-integral area
+        let triangle = [(0i32, 0i32), (3, 1), (1, 2)];
+        assert_eq!(signed_double_area(&triangle), Some(5));
+        assert_eq!(boundary_points(&triangle), Some(3));
+        assert_eq!(interior_points(&triangle), Some(2));
+    }
+
+    #[test]
+    fn test_locate_point() {
+        // U shape
+        let polygon = [
+            (0, 0),
+            (6, 0),
+            (6, 4),
+            (4, 4),
+            (4, 2),
+            (2, 2),
+            (2, 4),
+            (0, 4),
+        ];
+        assert_eq!(locate_point(&polygon, (1, 1)), PointLocation::Inside);
+        assert_eq!(locate_point(&polygon, (1, 3)), PointLocation::Inside);
+        assert_eq!(locate_point(&polygon, (3, 3)), PointLocation::Outside);
+        assert_eq!(locate_point(&polygon, (3, 2)), PointLocation::Boundary);
+        assert_eq!(locate_point(&polygon, (6, 4)), PointLocation::Boundary);
+        assert_eq!(locate_point(&polygon, (7, 0)), PointLocation::Outside);
+        assert_eq!(locate_point(&polygon, (-1, 2)), PointLocation::Outside);
+
+        // brute force Pick's theorem on the same shape
+        let mut inside = 0;
+        let mut boundary = 0;
+        for x in -1..8 {
+            for y in -1..6 {
+                match locate_point(&polygon, (x, y)) {
+                    PointLocation::Inside => inside += 1,
+                    PointLocation::Boundary => boundary += 1,
+                    PointLocation::Outside => {}
+                }
+            }
+        }
+        assert_eq!(interior_points(&polygon), Some(inside));
+        assert_eq!(boundary_points(&polygon), Some(boundary));
+    }
+
+    #[test]
+    fn test_large_coordinates() {
+        let m = i64::MAX / 2;
+        let square = [(-m, -m), (m, -m), (m, m), (-m, m)];
+        let side = 2 * m as i128;
+        assert_eq!(signed_double_area(&square), Some(2 * side * side));
+        assert_eq!(boundary_points(&square), Some(4 * side as u128));
+        assert_eq!(
+            interior_points(&square),
+            Some(((side - 1) * (side - 1)) as u128)
+        );
+
+        // the whole i64 plane, whose double area doesn't fit in i128
+        let (lo, hi) = (i64::MIN, i64::MAX);
+        let plane = [(lo, lo), (hi, lo), (hi, hi), (lo, hi)];
+        let side = u64::MAX as u128;
+        assert_eq!(signed_double_area(&plane), None);
+        assert_eq!(shoelace_area(&plane), Some(side * side));
+        assert_eq!(boundary_points(&plane), Some(4 * side));
+        assert_eq!(interior_points(&plane), Some((side - 1) * (side - 1)));
+        assert_eq!(locate_point(&plane, (0, 0)), PointLocation::Inside);
+
+        let far = [(i128::MAX - 2, 0i128), (i128::MAX, 0), (i128::MAX, 2)];
+        assert_eq!(signed_double_area(&far), Some(4));
+        assert_eq!(
+            locate_point(&far, (i128::MAX - 1, 0)),
+            PointLocation::Boundary
+        );
+
+        // thin triangle near i128::MAX / 2: every product overflows i128, but not the area
+        let m = i128::MAX / 2;
+        let thin = [(0, 0), (m, m + 1), (m - 1, m)];
+        assert_eq!(signed_double_area(&thin), Some(1));
+        assert_eq!(shoelace_area(&thin), Some(0));
+        assert_eq!(boundary_points(&thin), Some(3));
+        assert_eq!(interior_points(&thin), Some(0));
+        assert_eq!(locate_point(&thin, (m - 1, m)), PointLocation::Boundary);
+        assert_eq!(locate_point(&thin, (1, 1)), PointLocation::Outside);
+        assert_eq!(locate_point(&thin, (m / 2, m / 2)), PointLocation::Outside);
+
+        // square of side m around the origin, its area is far beyond u128
+        let huge = [(-m, -m), (m, -m), (m, m), (-m, m)];
+        assert_eq!(signed_double_area(&huge), None);
+        assert_eq!(interior_points(&huge), None);
+        assert_eq!(boundary_points(&huge), None);
+        assert_eq!(boundary_points(&huge[..2]), Some(4 * m as u128));
+        assert_eq!(locate_point(&huge, (m - 1, -m + 1)), PointLocation::Inside);
+        assert_eq!(locate_point(&huge, (m, 0)), PointLocation::Boundary);
+        assert_eq!(locate_point(&huge, (m + 1, 0)), PointLocation::Outside);
+    }
+}
@@ -0,0 +1,344 @@
+use std::collections::VecDeque;
+
+use rustworkx_core::petgraph::graph::{DiGraph, NodeIndex};
+
+use super::Interner;
+
+pub type NodeId = u32;
+
+const UNVISITED: u32 = u32::MAX;
+
+/// Directed graph stored as adjacency lists, nodes are identified by consecutive ids
+#[derive(Debug, Clone)]
+pub struct Graph<E = ()> {
+    children: Vec<Vec<(NodeId, E)>>,
+    parents: Vec<Vec<NodeId>>,
+}
+
+impl<E> Default for Graph<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> Graph<E> {
+    pub fn new() -> Self {
+        Self {
+            children: Vec::new(),
+            parents: Vec::new(),
+        }
+    }
+
+    pub fn with_nodes(n: usize) -> Self {
+        let mut graph = Self::new();
+        (0..n).for_each(|_| {
+            graph.add_node();
+        });
+        graph
+    }
+
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    pub fn add_node(&mut self) -> NodeId {
+        self.children.push(Vec::new());
+        self.parents.push(Vec::new());
+        (self.children.len() - 1) as NodeId
+    }
+
+    pub fn add_edge(&mut self, from: NodeId, to: NodeId, weight: E) {
+        self.children[from as usize].push((to, weight));
+        self.parents[to as usize].push(from);
+    }
+
+    pub fn children(&self, id: NodeId) -> &[(NodeId, E)] {
+        &self.children[id as usize]
+    }
+
+    pub fn parents(&self, id: NodeId) -> &[NodeId] {
+        &self.parents[id as usize]
+    }
+
+    /// Iterate over all the edges as (from, to, weight)
+    pub fn edges(&self) -> impl Iterator<Item = (NodeId, NodeId, &E)> {
+        self.children
+            .iter()
+            .enumerate()
+            .flat_map(|(from, children)| {
+                children
+                    .iter()
+                    .map(move |(to, weight)| (from as NodeId, *to, weight))
+            })
+    }
+
+    /// Nodes reachable from `start` in visiting order, with their distance in number of edges
+    pub fn bfs(&self, start: NodeId) -> Vec<(NodeId, u32)> {
+        let mut seen = vec![false; self.len()];
+        let mut order = Vec::new();
+        let mut queue = VecDeque::from([(start, 0)]);
+        seen[start as usize] = true;
+        while let Some((id, distance)) = queue.pop_front() {
+            order.push((id, distance));
+            for (child, _) in self.children(id) {
+                if !seen[*child as usize] {
+                    seen[*child as usize] = true;
+                    queue.push_back((*child, distance + 1));
+                }
+            }
+        }
+        order
+    }
+
+    /// Nodes reachable from `start` in depth-first preorder
+    pub fn dfs(&self, start: NodeId) -> Vec<NodeId> {
+        let mut seen = vec![false; self.len()];
+        let mut order = Vec::new();
+        let mut stack = vec![start];
+        while let Some(id) = stack.pop() {
+            if seen[id as usize] {
+                continue;
+            }
+            seen[id as usize] = true;
+            order.push(id);
+            // reversed so that children are visited in insertion order
+            stack.extend(self.children(id).iter().rev().map(|(child, _)| *child));
+        }
+        order
+    }
+
+    /// For each node, whether it can be reached from `start`
+    pub fn reachable(&self, start: NodeId) -> Vec<bool> {
+        let mut seen = vec![false; self.len()];
+        self.dfs(start)
+            .into_iter()
+            .for_each(|id| seen[id as usize] = true);
+        seen
+    }
+
+    /// Topological order of the nodes (Kahn's algorithm), None if the graph has a cycle
+    pub fn topological_sort(&self) -> Option<Vec<NodeId>> {
+        let mut in_degree = self.parents.iter().map(|p| p.len()).collect::<Vec<_>>();
+        let mut order = (0..self.len() as NodeId)
+            .filter(|id| in_degree[*id as usize] == 0)
+            .collect::<Vec<_>>();
+        let mut i = 0;
+        while i < order.len() {
+            for (child, _) in self.children(order[i]) {
+                in_degree[*child as usize] -= 1;
+                if in_degree[*child as usize] == 0 {
+                    order.push(*child);
+                }
+            }
+            i += 1;
+        }
+        (order.len() == self.len()).then_some(order)
+    }
+
+    /// Strongly connected components (iterative Tarjan), in reverse topological order
+    pub fn strongly_connected_components(&self) -> Vec<Vec<NodeId>> {
+        let n = self.len();
+        let mut index = vec![UNVISITED; n];
+        let mut low = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut stack = Vec::new();
+        let mut components = Vec::new();
+        let mut next_index = 0;
+
+        for root in 0..n as NodeId {
+            if index[root as usize] != UNVISITED {
+                continue;
+            }
+            // (node, next child to explore)
+            let mut call_stack = vec![(root, 0)];
+            index[root as usize] = next_index;
+            low[root as usize] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root as usize] = true;
+
+            while let Some((v, i)) = call_stack.last_mut() {
+                let v = *v;
+                if let Some((w, _)) = self.children(v).get(*i) {
+                    let w = *w;
+                    *i += 1;
+                    if index[w as usize] == UNVISITED {
+                        index[w as usize] = next_index;
+                        low[w as usize] = next_index;
+                        next_index += 1;
+                        stack.push(w);
+                        on_stack[w as usize] = true;
+                        call_stack.push((w, 0));
+                    } else if on_stack[w as usize] {
+                        low[v as usize] = low[v as usize].min(index[w as usize]);
+                    }
+                    continue;
+                }
+
+                call_stack.pop();
+                if let Some((u, _)) = call_stack.last() {
+                    low[*u as usize] = low[*u as usize].min(low[v as usize]);
+                }
+                if low[v as usize] == index[v as usize] {
+                    let mut component = Vec::new();
+                    loop {
+                        let w = stack.pop().unwrap();
+                        on_stack[w as usize] = false;
+                        component.push(w);
+                        if w == v {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+        components
+    }
+
+    /// Copy into a petgraph graph, where node `id` becomes `NodeIndex::new(id)`
+    pub fn to_petgraph(&self) -> DiGraph<NodeId, E>
+    where
+        E: Clone,
+    {
+        let mut graph = DiGraph::with_capacity(self.len(), 0);
+        (0..self.len() as NodeId).for_each(|id| {
+            graph.add_node(id);
+        });
+        for (from, to, weight) in self.edges() {
+            graph.add_edge(
+                NodeIndex::new(from as usize),
+                NodeIndex::new(to as usize),
+                weight.clone(),
+            );
+        }
+        graph
+    }
+}
+
+/// Build a graph from named nodes, giving each new name the next id
+#[derive(Debug)]
+pub struct GraphBuilder<'a, E = ()> {
+    graph: Graph<E>,
+    names: Interner<'a, NodeId>,
+}
+
+impl<'a, E> Default for GraphBuilder<'a, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, E> GraphBuilder<'a, E> {
+    pub fn new() -> Self {
+        Self {
+            graph: Graph::new(),
+            names: Interner::new(),
+        }
+    }
+
+    pub fn get_or_create_node_id(&mut self, name: &'a [u8]) -> NodeId {
+        let id = self.names.intern(name);
+        if id as usize == self.graph.len() {
+            self.graph.add_node();
+        }
+        id
+    }
+
+    pub fn id(&self, name: &[u8]) -> Option<NodeId> {
+        self.names.get(name)
+    }
+
+    pub fn name(&self, id: NodeId) -> &'a [u8] {
+        self.names.name(id)
+    }
+
+    pub fn add_edge(&mut self, from: &'a [u8], to: &'a [u8], weight: E) {
+        let from = self.get_or_create_node_id(from);
+        let to = self.get_or_create_node_id(to);
+        self.graph.add_edge(from, to, weight);
+    }
+
+    pub fn graph(&self) -> &Graph<E> {
+        &self.graph
+    }
+
+    pub fn graph_mut(&mut self) -> &mut Graph<E> {
+        &mut self.graph
+    }
+
+    pub fn build(self) -> Graph<E> {
+        self.graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> (Graph, Vec<NodeId>) {
+        // a -> b -> c -> a, c -> d -> e, e -> d, f alone
+        let mut builder = GraphBuilder::new();
+        for (from, to) in [("a", "b"), ("b", "c"), ("c", "a"), ("c", "d")] {
+            builder.add_edge(from.as_bytes(), to.as_bytes(), ());
+        }
+        builder.add_edge(b"d", b"e", ());
+        builder.add_edge(b"e", b"d", ());
+        builder.get_or_create_node_id(b"f");
+        let ids = ["a", "b", "c", "d", "e", "f"]
+            .iter()
+            .map(|n| builder.id(n.as_bytes()).unwrap())
+            .collect();
+        assert_eq!(builder.name(2), b"c");
+        (builder.build(), ids)
+    }
+
+    #[test]
+    fn test_base() {
+        let (graph, ids) = example();
+        assert_eq!(graph.len(), 6);
+        assert_eq!(ids, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(graph.parents(0), &[2]);
+
+        assert_eq!(graph.bfs(0), vec![(0, 0), (1, 1), (2, 2), (3, 3), (4, 4)]);
+        assert_eq!(graph.dfs(2), vec![2, 0, 1, 3, 4]);
+        assert_eq!(
+            graph.reachable(3),
+            vec![false, false, false, true, true, false]
+        );
+        assert_eq!(graph.topological_sort(), None);
+
+        let mut components = graph.strongly_connected_components();
+        components.iter_mut().for_each(|c| c.sort());
+        assert_eq!(components, vec![vec![3, 4], vec![0, 1, 2], vec![5]]);
+
+        let petgraph = graph.to_petgraph();
+        assert_eq!(petgraph.node_count(), 6);
+        assert_eq!(petgraph.edge_count(), 6);
+        assert!(petgraph.contains_edge(NodeIndex::new(2), NodeIndex::new(3)));
+    }
+
+    #[test]
+    fn test_topological_sort() {
+        let mut graph = Graph::with_nodes(5);
+        for (from, to) in [(3, 1), (1, 0), (3, 4), (4, 0), (2, 4)] {
+            graph.add_edge(from, to, ());
+        }
+        let order = graph.topological_sort().unwrap();
+        let position = |id: NodeId| order.iter().position(|v| *v == id).unwrap();
+        for (from, to, _) in graph.edges() {
+            assert!(position(from) < position(to));
+        }
+        // each node is its own component in a DAG, and they come in reverse topological order
+        let components = graph.strongly_connected_components();
+        assert_eq!(components.len(), 5);
+        for (from, to, _) in graph.edges() {
+            let component = |id| components.iter().position(|c| c[0] == id).unwrap();
+            assert!(component(to) < component(from));
+        }
+    }
+}
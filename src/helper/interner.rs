@@ -0,0 +1,207 @@
+use std::fmt::Debug;
+
+use ahash::{HashMap, HashMapExt};
+
+/// Integer type usable as a dense id, `MAX` is never a valid id
+pub trait Id: Copy + Eq + Debug {
+    const MAX: Self;
+    fn from_usize(v: usize) -> Self;
+    fn to_usize(self) -> usize;
+}
+
+impl Id for u16 {
+    const MAX: Self = u16::MAX;
+
+    fn from_usize(v: usize) -> Self {
+        // MAX marks the empty slots of FixedInterner
+        v.try_into()
+            .ok()
+            .filter(|id| *id != u16::MAX)
+            .expect("too many names for u16 ids")
+    }
+
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+}
+
+impl Id for u32 {
+    const MAX: Self = u32::MAX;
+
+    fn from_usize(v: usize) -> Self {
+        // MAX marks the empty slots of FixedInterner
+        v.try_into()
+            .ok()
+            .filter(|id| *id != u32::MAX)
+            .expect("too many names for u32 ids")
+    }
+
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+}
+
+/// Give each distinct name the next id, starting from 0
+#[derive(Debug, Clone)]
+pub struct Interner<'a, I = u32> {
+    ids: HashMap<&'a [u8], I>,
+    names: Vec<&'a [u8]>,
+}
+
+impl<'a, I: Id> Default for Interner<'a, I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, I: Id> Interner<'a, I> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            ids: HashMap::with_capacity(capacity),
+            names: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn intern(&mut self, name: &'a [u8]) -> I {
+        *self.ids.entry(name).or_insert_with(|| {
+            self.names.push(name);
+            I::from_usize(self.names.len() - 1)
+        })
+    }
+
+    pub fn get(&self, name: &[u8]) -> Option<I> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: I) -> &'a [u8] {
+        self.names[id.to_usize()]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+const N_SYMBOLS: usize = 62;
+
+// digits, then uppercase and lowercase letters
+fn symbol(b: u8) -> usize {
+    match b {
+        b'0'..=b'9' => (b - b'0') as usize,
+        b'A'..=b'Z' => (b - b'A') as usize + 10,
+        b'a'..=b'z' => (b - b'a') as usize + 36,
+        _ => panic!("{:?} is not alphanumeric", b as char),
+    }
+}
+
+/// Largest lookup table of a `FixedInterner`, which limits labels to 3 bytes
+const MAX_TABLE_LEN: usize = 1 << 20;
+
+/// Interner for alphanumeric labels of exactly `N` bytes, like `AAA` in day 8.
+/// Ids are found with a direct lookup in a table of 62^N entries instead of hashing, so `N` can
+/// be at most 3 (238,328 entries), a larger `N` doesn't compile. Use `Interner` for longer labels.
+#[derive(Debug, Clone)]
+pub struct FixedInterner<const N: usize, I = u16> {
+    table: Vec<I>,
+    names: Vec<[u8; N]>,
+}
+
+impl<const N: usize, I: Id> Default for FixedInterner<N, I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, I: Id> FixedInterner<N, I> {
+    const TABLE_LEN: usize = match N_SYMBOLS.checked_pow(N as u32) {
+        Some(len) if len <= MAX_TABLE_LEN => len,
+        _ => panic!("FixedInterner labels are too long for a lookup table"),
+    };
+
+    pub fn new() -> Self {
+        Self {
+            table: vec![I::MAX; Self::TABLE_LEN],
+            names: Vec::new(),
+        }
+    }
+
+    fn slot(name: &[u8]) -> usize {
+        assert_eq!(name.len(), N, "label {name:?} doesn't have {N} bytes");
+        name.iter().fold(0, |acc, b| acc * N_SYMBOLS + symbol(*b))
+    }
+
+    pub fn intern(&mut self, name: &[u8]) -> I {
+        let slot = Self::slot(name);
+        if self.table[slot] == I::MAX {
+            self.table[slot] = I::from_usize(self.names.len());
+            self.names.push(name.try_into().unwrap());
+        }
+        self.table[slot]
+    }
+
+    pub fn get(&self, name: &[u8]) -> Option<I> {
+        let id = self.table[Self::slot(name)];
+        (id != I::MAX).then_some(id)
+    }
+
+    pub fn name(&self, id: I) -> &[u8] {
+        &self.names[id.to_usize()]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base() {
+        let mut interner = Interner::<u16>::new();
+        assert_eq!(interner.intern(b"broadcaster"), 0);
+        assert_eq!(interner.intern(b"rx"), 1);
+        assert_eq!(interner.intern(b"broadcaster"), 0);
+        assert_eq!(interner.get(b"rx"), Some(1));
+        assert_eq!(interner.get(b"jqt"), None);
+        assert_eq!(interner.name(1), b"rx");
+        assert_eq!(interner.len(), 2);
+
+        let mut interner = FixedInterner::<3>::new();
+        assert_eq!(interner.intern(b"AAA"), 0);
+        assert_eq!(interner.intern(b"11Z"), 1);
+        assert_eq!(interner.intern(b"ZZZ"), 2);
+        assert_eq!(interner.intern(b"AAA"), 0);
+        assert_eq!(interner.get(b"11z"), None);
+        assert_eq!(interner.intern(b"11z"), 3);
+        assert_eq!(interner.name(1), b"11Z");
+        assert_eq!(interner.get(b"11A"), None);
+        assert_eq!(interner.name(2), b"ZZZ");
+        assert_eq!(interner.len(), 4);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_wrong_length() {
+        FixedInterner::<3, u32>::new().intern(b"AAAA");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_max_id() {
+        u16::from_usize(u16::MAX as usize);
+    }
+}
@@ -0,0 +1,549 @@
+use std::ops::Range;
+
+use num::PrimInt;
+
+/// Set of integers stored as sorted, disjoint and non adjacent half-open ranges.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RangeSet<T> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T: PrimInt> Default for RangeSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: PrimInt> FromIterator<Range<T>> for RangeSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut ranges = iter
+            .into_iter()
+            .filter(|r| r.start < r.end)
+            .collect::<Vec<_>>();
+        ranges.sort_unstable_by_key(|r| r.start);
+        Self::from_sorted(ranges)
+    }
+}
+
+impl<T: PrimInt> RangeSet<T> {
+    pub fn new() -> Self {
+        Self { ranges: Vec::new() }
+    }
+
+    /// Build the set from non empty ranges sorted by start, merging overlapping and adjacent ones
+    fn from_sorted(sorted: impl IntoIterator<Item = Range<T>>) -> Self {
+        let mut ranges: Vec<Range<T>> = Vec::new();
+        for r in sorted {
+            match ranges.last_mut() {
+                Some(last) if r.start <= last.end => last.end = last.end.max(r.end),
+                _ => ranges.push(r),
+            }
+        }
+        Self { ranges }
+    }
+
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Number of integers in the set
+    pub fn len(&self) -> T {
+        self.ranges
+            .iter()
+            .fold(T::zero(), |acc, r| acc + (r.end - r.start))
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.ranges.first().map(|r| r.start)
+    }
+
+    pub fn max(&self) -> Option<T> {
+        self.ranges.last().map(|r| r.end - T::one())
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let i = self.ranges.partition_point(|r| r.end <= value);
+        i < self.ranges.len() && self.ranges[i].start <= value
+    }
+
+    pub fn insert(&mut self, range: Range<T>) {
+        if range.start >= range.end {
+            return;
+        }
+        // first range that can be merged, then first range that is after the new one
+        let first = self.ranges.partition_point(|r| r.end < range.start);
+        let last = self.ranges.partition_point(|r| r.start <= range.end);
+        let mut merged = range;
+        if first < last {
+            merged.start = merged.start.min(self.ranges[first].start);
+            merged.end = merged.end.max(self.ranges[last - 1].end);
+        }
+        self.ranges.splice(first..last, [merged]);
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut a = self.ranges.iter().peekable();
+        let mut b = other.ranges.iter().peekable();
+        let merged = std::iter::from_fn(|| match (a.peek(), b.peek()) {
+            (Some(x), Some(y)) if x.start <= y.start => a.next(),
+            (Some(_), Some(_)) => b.next(),
+            (Some(_), None) => a.next(),
+            (None, _) => b.next(),
+        });
+        Self::from_sorted(merged.cloned())
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (&self.ranges[i], &other.ranges[j]);
+            let start = a.start.max(b.start);
+            let end = a.end.min(b.end);
+            if start < end {
+                ranges.push(start..end);
+            }
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        Self { ranges }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let mut j = 0;
+        for r in &self.ranges {
+            let mut start = r.start;
+            // skip the ranges of other that end before the current one
+            while j < other.ranges.len() && other.ranges[j].end <= start {
+                j += 1;
+            }
+            let mut k = j;
+            while k < other.ranges.len() && other.ranges[k].start < r.end {
+                if other.ranges[k].start > start {
+                    ranges.push(start..other.ranges[k].start);
+                }
+                start = start.max(other.ranges[k].end);
+                k += 1;
+            }
+            if start < r.end {
+                ranges.push(start..r.end);
+            }
+        }
+        Self { ranges }
+    }
+
+    /// Split the set into the values lower than `at` and the values greater or equal to `at`
+    pub fn split_at(&self, at: T) -> (Self, Self) {
+        let i = self.ranges.partition_point(|r| r.end <= at);
+        let mut lower = self.ranges[..i].to_vec();
+        let mut upper = self.ranges[i..].to_vec();
+        if let Some(r) = upper.first_mut() {
+            if r.start < at {
+                lower.push(r.start..at);
+                r.start = at;
+            }
+        }
+        (Self { ranges: lower }, Self { ranges: upper })
+    }
+}
+
+/// `v - from + to` without computing `v - from` or `to - from` alone, as either can overflow a
+/// signed type when the result doesn't. `v` must be at least `from`.
+fn checked_translate<T: PrimInt>(v: T, from: T, to: T) -> Option<T> {
+    if from == to {
+        return Some(v);
+    }
+    v.checked_sub(&from)
+        .and_then(|d| to.checked_add(&d))
+        .or_else(|| to.checked_sub(&from).and_then(|d| v.checked_add(&d)))
+        .or_else(|| from.checked_sub(&to).and_then(|d| v.checked_sub(&d)))
+}
+
+fn translate<T: PrimInt>(v: T, from: T, to: T) -> T {
+    checked_translate(v, from, to).expect("overflow in RangeMap")
+}
+
+/// Piecewise-linear mapping: each source range is shifted to start at its destination,
+/// values outside of every source range are left unchanged.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RangeMap<T> {
+    // (source, destination start), sorted by source and non overlapping
+    segments: Vec<(Range<T>, T)>,
+}
+
+impl<T: PrimInt> Default for RangeMap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: PrimInt> FromIterator<(Range<T>, T)> for RangeMap<T> {
+    fn from_iter<I: IntoIterator<Item = (Range<T>, T)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        iter.into_iter()
+            .for_each(|(source, dest)| map.insert(source, dest));
+        map
+    }
+}
+
+impl<T: PrimInt> RangeMap<T> {
+    pub fn new() -> Self {
+        Self {
+            segments: Vec::new(),
+        }
+    }
+
+    pub fn segments(&self) -> &[(Range<T>, T)] {
+        &self.segments
+    }
+
+    /// Map every value of `source` to `dest + (value - source.start)`.
+    /// Panics if `source` overlaps an existing segment or if its image doesn't fit in `T`.
+    pub fn insert(&mut self, source: Range<T>, dest: T) {
+        if source.start >= source.end {
+            return;
+        }
+        assert!(
+            checked_translate(source.end, source.start, dest).is_some(),
+            "segment image overflows in RangeMap"
+        );
+        let i = self
+            .segments
+            .partition_point(|(s, _)| s.start < source.start);
+        assert!(
+            (i == 0 || self.segments[i - 1].0.end <= source.start)
+                && (i == self.segments.len() || source.end <= self.segments[i].0.start),
+            "overlapping segments in RangeMap"
+        );
+        self.segments.insert(i, (source, dest));
+    }
+
+    pub fn get(&self, value: T) -> T {
+        let i = self.segments.partition_point(|(s, _)| s.end <= value);
+        match self.segments.get(i) {
+            Some((s, dest)) if s.start <= value => translate(value, s.start, *dest),
+            _ => value,
+        }
+    }
+
+    /// Image of a whole set through the mapping
+    pub fn apply(&self, set: &RangeSet<T>) -> RangeSet<T> {
+        let mut out = Vec::new();
+        for r in set.ranges() {
+            let mut start = r.start;
+            let mut i = self.segments.partition_point(|(s, _)| s.end <= start);
+            while start < r.end {
+                match self.segments.get(i) {
+                    Some((s, dest)) if s.start <= start => {
+                        let end = s.end.min(r.end);
+                        out.push(translate(start, s.start, *dest)..translate(end, s.start, *dest));
+                        start = end;
+                        i += 1;
+                    }
+                    Some((s, _)) if s.start < r.end => {
+                        out.push(start..s.start);
+                        start = s.start;
+                    }
+                    _ => {
+                        out.push(start..r.end);
+                        start = r.end;
+                    }
+                }
+            }
+        }
+        out.into_iter().collect()
+    }
+
+    /// Segments covering the whole domain, gaps included as identity segments
+    fn pieces(&self) -> Vec<(Range<T>, T)> {
+        let mut pieces = Vec::with_capacity(2 * self.segments.len() + 1);
+        let mut start = T::min_value();
+        for (s, dest) in self.segments.iter() {
+            if start < s.start {
+                pieces.push((start..s.start, start));
+            }
+            pieces.push((s.clone(), *dest));
+            start = s.end;
+        }
+        if start < T::max_value() {
+            pieces.push((start..T::max_value(), start));
+        }
+        pieces
+    }
+
+    /// Mapping equivalent to applying `self` then `then`
+    pub fn compose(&self, then: &Self) -> Self {
+        let mut segments: Vec<(Range<T>, T)> = Vec::new();
+        for (source, dest) in self.pieces() {
+            let image_end = translate(source.end, source.start, dest);
+            for (s, then_dest) in then.pieces() {
+                let start = dest.max(s.start);
+                let end = image_end.min(s.end);
+                if start >= end {
+                    continue;
+                }
+                let from = translate(start, dest, source.start)..translate(end, dest, source.start);
+                let to = translate(start, s.start, then_dest);
+                if from.start == to {
+                    continue;
+                }
+                match segments.last_mut() {
+                    // merge with the previous segment when it continues it
+                    Some((prev, prev_dest))
+                        if prev.end == from.start
+                            && translate(prev.end, prev.start, *prev_dest) == to =>
+                    {
+                        prev.end = from.end;
+                    }
+                    _ => segments.push((from, to)),
+                }
+            }
+        }
+        Self { segments }
+    }
+
+    /// Values mapped into `set`
+    pub fn preimage(&self, set: &RangeSet<T>) -> RangeSet<T> {
+        let mut out = RangeSet::new();
+        for (source, dest) in self.pieces() {
+            let image = RangeSet::from_iter(Some(dest..translate(source.end, source.start, dest)));
+            for r in image.intersection(set).ranges() {
+                out.insert(
+                    translate(r.start, dest, source.start)..translate(r.end, dest, source.start),
+                );
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use proptest::prelude::*;
+
+    use super::*;
+
+    const DOMAIN: u16 = 64;
+
+    fn ranges() -> impl Strategy<Value = Vec<Range<u16>>> {
+        prop::collection::vec((0..DOMAIN, 0..16u16), 0..8)
+            .prop_map(|v| v.into_iter().map(|(s, l)| s..s + l).collect())
+    }
+
+    // non overlapping segments, keeping every value below 2 * DOMAIN
+    fn segments() -> impl Strategy<Value = Vec<(Range<u16>, u16)>> {
+        (
+            prop::collection::btree_set(0..DOMAIN, 0..8),
+            prop::collection::vec(0..DOMAIN, 8),
+        )
+            .prop_map(|(cuts, dests)| {
+                let cuts = cuts.into_iter().collect::<Vec<_>>();
+                cuts.windows(2)
+                    .zip(dests)
+                    .map(|(w, dest)| (w[0]..w[1], dest))
+                    .collect()
+            })
+    }
+
+    // the same as `segments`, around 0 for signed values
+    fn signed_segments() -> impl Strategy<Value = Vec<(Range<i64>, i64)>> {
+        segments().prop_map(|v| {
+            v.into_iter()
+                .map(|(r, dest)| {
+                    let shift = |x: u16| x as i64 - DOMAIN as i64;
+                    (shift(r.start)..shift(r.end), shift(dest))
+                })
+                .collect()
+        })
+    }
+
+    fn oracle(ranges: &[Range<u16>]) -> BTreeSet<u16> {
+        ranges.iter().flat_map(|r| r.clone()).collect()
+    }
+
+    fn values(set: &RangeSet<u16>) -> BTreeSet<u16> {
+        oracle(set.ranges())
+    }
+
+    fn assert_normalized(set: &RangeSet<u16>) {
+        for r in set.ranges() {
+            assert!(r.start < r.end);
+        }
+        for w in set.ranges().windows(2) {
+            assert!(w[0].end < w[1].start);
+        }
+    }
+
+    #[test]
+    fn test_base() {
+        let mut set: RangeSet<u64> = [10..20, 30..40].into_iter().collect();
+        set.insert(20..30);
+        assert_eq!(set, RangeSet::from_iter(Some(10..40)));
+        assert_eq!(set.len(), 30);
+
+        let hole = RangeSet::from_iter(Some(15..18));
+        assert_eq!(set.difference(&hole).ranges(), &[10..15, 18..40]);
+
+        let (lower, upper) = set.split_at(25);
+        assert_eq!((lower.min(), lower.max()), (Some(10), Some(24)));
+        assert_eq!((upper.min(), upper.max()), (Some(25), Some(39)));
+
+        // seed-to-soil map of the day 5 example
+        let map: RangeMap<u64> = [(98..100, 50), (50..98, 52)].into_iter().collect();
+        assert_eq!(map.get(79), 81);
+        assert_eq!(map.get(10), 10);
+        let seeds: RangeSet<u64> = [79..93, 55..68].into_iter().collect();
+        assert_eq!(map.apply(&seeds).ranges(), &[57..70, 81..95]);
+    }
+
+    proptest! {
+        #[test]
+        fn test_insert(a in ranges()) {
+            let mut set = RangeSet::new();
+            a.iter().for_each(|r| set.insert(r.clone()));
+            assert_normalized(&set);
+            prop_assert_eq!(values(&set), oracle(&a));
+            prop_assert_eq!(set.len() as usize, oracle(&a).len());
+            for v in 0..DOMAIN + 16 {
+                prop_assert_eq!(set.contains(v), oracle(&a).contains(&v));
+            }
+        }
+
+        #[test]
+        fn test_union(a in ranges(), b in ranges()) {
+            let set = RangeSet::from_iter(a.clone()).union(&b.iter().cloned().collect());
+            assert_normalized(&set);
+            prop_assert_eq!(values(&set), oracle(&a).union(&oracle(&b)).cloned().collect());
+        }
+
+        #[test]
+        fn test_intersection(a in ranges(), b in ranges()) {
+            let set = RangeSet::from_iter(a.clone()).intersection(&b.iter().cloned().collect());
+            assert_normalized(&set);
+            prop_assert_eq!(values(&set), oracle(&a).intersection(&oracle(&b)).cloned().collect());
+        }
+
+        #[test]
+        fn test_difference(a in ranges(), b in ranges()) {
+            let set = RangeSet::from_iter(a.clone()).difference(&b.iter().cloned().collect());
+            assert_normalized(&set);
+            prop_assert_eq!(values(&set), oracle(&a).difference(&oracle(&b)).cloned().collect());
+        }
+
+        #[test]
+        fn test_split_at(a in ranges(), at in 0..DOMAIN + 16) {
+            let (lower, upper) = RangeSet::from_iter(a.clone()).split_at(at);
+            assert_normalized(&lower);
+            assert_normalized(&upper);
+            prop_assert_eq!(values(&lower), oracle(&a).into_iter().filter(|&v| v < at).collect());
+            prop_assert_eq!(values(&upper), oracle(&a).into_iter().filter(|&v| v >= at).collect());
+        }
+
+        #[test]
+        fn test_apply(a in ranges(), cuts in prop::collection::btree_set(0..DOMAIN, 0..8), dests in prop::collection::vec(0..DOMAIN, 8)) {
+            // consecutive cuts give non overlapping source ranges
+            let cuts = cuts.into_iter().collect::<Vec<_>>();
+            let segments = cuts
+                .windows(2)
+                .zip(dests)
+                .map(|(w, dest)| (w[0]..w[1], dest))
+                .collect::<Vec<_>>();
+            let brute_force = |v: u16| {
+                segments
+                    .iter()
+                    .find(|(s, _)| s.contains(&v))
+                    .map_or(v, |(s, dest)| dest + (v - s.start))
+            };
+            let map = segments.iter().cloned().collect::<RangeMap<u16>>();
+            let set = map.apply(&a.iter().cloned().collect());
+            assert_normalized(&set);
+            prop_assert_eq!(values(&set), oracle(&a).into_iter().map(brute_force).collect());
+            for v in 0..DOMAIN + 16 {
+                prop_assert_eq!(map.get(v), brute_force(v));
+            }
+        }
+
+        #[test]
+        fn test_compose(a in segments(), b in segments(), set in ranges()) {
+            let a = a.into_iter().collect::<RangeMap<u16>>();
+            let b = b.into_iter().collect::<RangeMap<u16>>();
+            let composed = a.compose(&b);
+            for w in composed.segments().windows(2) {
+                prop_assert!(w[0].0.end <= w[1].0.start);
+            }
+            for v in 0..DOMAIN * 2 {
+                prop_assert_eq!(composed.get(v), b.get(a.get(v)));
+            }
+
+            let set = set.into_iter().collect::<RangeSet<u16>>();
+            let preimage = composed.preimage(&set);
+            assert_normalized(&preimage);
+            for v in 0..DOMAIN * 2 {
+                prop_assert_eq!(preimage.contains(v), set.contains(composed.get(v)));
+            }
+        }
+
+        #[test]
+        fn test_compose_signed(a in signed_segments(), b in signed_segments(), set in ranges()) {
+            let a = a.into_iter().collect::<RangeMap<i64>>();
+            let b = b.into_iter().collect::<RangeMap<i64>>();
+            let composed = a.compose(&b);
+            let domain = -2 * DOMAIN as i64..2 * DOMAIN as i64;
+            for v in domain.clone() {
+                prop_assert_eq!(composed.get(v), b.get(a.get(v)));
+            }
+
+            let set = set
+                .into_iter()
+                .map(|r| r.start as i64 - DOMAIN as i64..r.end as i64 - DOMAIN as i64)
+                .collect::<RangeSet<i64>>();
+            let preimage = composed.preimage(&set);
+            for v in domain {
+                prop_assert_eq!(preimage.contains(v), set.contains(composed.get(v)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_extreme_signed() {
+        let (min, max) = (i64::MIN, i64::MAX);
+        // swap the two halves of the domain, except the last value which isn't covered
+        let a = RangeMap::from_iter([(min..-1, 0), (-1..max, min)]);
+        assert_eq!(
+            (a.get(min), a.get(-2), a.get(-1), a.get(max - 1)),
+            (0, max - 1, min, -1)
+        );
+        let twice = a.compose(&a);
+        for v in [min, min + 1, -2, -1, 0, 1, max - 1, max] {
+            assert_eq!(twice.get(v), a.get(a.get(v)), "{v}");
+        }
+        // shifting by one and back is the identity, except for max - 1 which up leaves alone
+        let up = RangeMap::from_iter([(min..max - 1, min + 1)]);
+        let down = RangeMap::from_iter([(min + 1..max, min)]);
+        assert_eq!(up.compose(&down).segments(), [(max - 1..max, max - 2)]);
+        let set = RangeSet::from_iter([min..min + 2, max - 2..max]);
+        assert_eq!(up.preimage(&set).ranges(), [min..min + 1, max - 3..max]);
+    }
+
+    #[test]
+    #[should_panic(expected = "overflows")]
+    fn test_insert_overflow() {
+        RangeMap::from_iter([(0..10u8, 250)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "overflows")]
+    fn test_insert_overflow_signed() {
+        RangeMap::from_iter([(i64::MIN..0, 1)]);
+    }
+}
@@ -0,0 +1,260 @@
+use std::{cmp::Reverse, collections::BinaryHeap, ops::Add};
+
+use num::Zero;
+
+/// Map each state to a unique index in `0..n_states`, used to track the visited states in a bitset
+pub trait DenseIndex {
+    fn index(&self) -> usize;
+}
+
+/// Result of a search: the total cost and the states from the start to the goal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<S, C> {
+    pub cost: C,
+    pub states: Vec<S>,
+}
+
+trait Frontier<C> {
+    fn push(&mut self, priority: C, id: u32);
+    fn pop(&mut self) -> Option<u32>;
+}
+
+impl<C: Ord> Frontier<C> for BinaryHeap<Reverse<(C, u32)>> {
+    fn push(&mut self, priority: C, id: u32) {
+        BinaryHeap::push(self, Reverse((priority, id)));
+    }
+
+    fn pop(&mut self) -> Option<u32> {
+        BinaryHeap::pop(self).map(|Reverse((_, id))| id)
+    }
+}
+
+/// Queue indexed by priority, for small integer costs. Priorities must never be lower
+/// than the last one popped, which holds for Dijkstra and A* with a consistent heuristic.
+#[derive(Debug, Default)]
+struct BucketQueue {
+    buckets: Vec<Vec<u32>>,
+    current: usize,
+}
+
+impl Frontier<usize> for BucketQueue {
+    fn push(&mut self, priority: usize, id: u32) {
+        debug_assert!(priority >= self.current);
+        if priority >= self.buckets.len() {
+            self.buckets.resize_with(priority + 1, Vec::new);
+        }
+        self.buckets[priority].push(id);
+    }
+
+    fn pop(&mut self) -> Option<u32> {
+        while self.current < self.buckets.len() {
+            if let Some(id) = self.buckets[self.current].pop() {
+                return Some(id);
+            }
+            self.current += 1;
+        }
+        None
+    }
+}
+
+fn search<S, C, Q, I>(
+    mut queue: Q,
+    starts: impl IntoIterator<Item = S>,
+    n_states: usize,
+    mut neighbors: impl FnMut(&S) -> I,
+    mut heuristic: impl FnMut(&S) -> C,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S, C>>
+where
+    S: DenseIndex + Clone,
+    C: Copy + Ord + Add<Output = C> + Zero,
+    Q: Frontier<C>,
+    I: IntoIterator<Item = (S, C)>,
+{
+    // every pushed state is stored once with its cost and the id of its parent
+    let mut arena: Vec<(S, C, u32)> = Vec::new();
+    let mut seen = vec![0u64; n_states.div_ceil(64)];
+    let is_seen = |seen: &[u64], i: usize| (seen[i / 64] >> (i % 64)) & 1 == 1;
+
+    for start in starts {
+        queue.push(heuristic(&start), arena.len() as u32);
+        arena.push((start, C::zero(), u32::MAX));
+    }
+
+    while let Some(id) = queue.pop() {
+        let (state, cost, _) = &arena[id as usize];
+        let i = state.index();
+        if is_seen(&seen, i) {
+            continue;
+        }
+        seen[i / 64] |= 1 << (i % 64);
+
+        if is_goal(state) {
+            let cost = *cost;
+            let mut states = vec![];
+            let mut current = id;
+            while current != u32::MAX {
+                states.push(arena[current as usize].0.clone());
+                current = arena[current as usize].2;
+            }
+            states.reverse();
+            return Some(Path { cost, states });
+        }
+
+        let cost = *cost;
+        for (next, step) in neighbors(state) {
+            if is_seen(&seen, next.index()) {
+                continue;
+            }
+            let next_cost = cost + step;
+            queue.push(next_cost + heuristic(&next), arena.len() as u32);
+            arena.push((next, next_cost, id));
+        }
+    }
+    None
+}
+
+/// Cheapest path from any of the starts to a goal state
+pub fn dijkstra<S, C, I>(
+    starts: impl IntoIterator<Item = S>,
+    n_states: usize,
+    neighbors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S, C>>
+where
+    S: DenseIndex + Clone,
+    C: Copy + Ord + Add<Output = C> + Zero,
+    I: IntoIterator<Item = (S, C)>,
+{
+    search(
+        BinaryHeap::new(),
+        starts,
+        n_states,
+        neighbors,
+        |_| C::zero(),
+        is_goal,
+    )
+}
+
+/// Cheapest path from any of the starts to a goal state.
+/// The heuristic must never overestimate the remaining cost and be consistent.
+pub fn astar<S, C, I>(
+    starts: impl IntoIterator<Item = S>,
+    n_states: usize,
+    neighbors: impl FnMut(&S) -> I,
+    heuristic: impl FnMut(&S) -> C,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S, C>>
+where
+    S: DenseIndex + Clone,
+    C: Copy + Ord + Add<Output = C> + Zero,
+    I: IntoIterator<Item = (S, C)>,
+{
+    search(
+        BinaryHeap::new(),
+        starts,
+        n_states,
+        neighbors,
+        heuristic,
+        is_goal,
+    )
+}
+
+/// Same as `dijkstra`, with a bucket queue instead of a binary heap.
+/// Faster when the total cost stays small.
+pub fn dijkstra_buckets<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    n_states: usize,
+    neighbors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S, usize>>
+where
+    S: DenseIndex + Clone,
+    I: IntoIterator<Item = (S, usize)>,
+{
+    search(
+        BucketQueue::default(),
+        starts,
+        n_states,
+        neighbors,
+        |_| 0,
+        is_goal,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const W: usize = 5;
+    // cost to enter each tile, 0 is a wall
+    const GRID: [[u8; W]; 4] = [
+        [1, 1, 9, 1, 1],
+        [2, 0, 1, 0, 1],
+        [1, 0, 1, 0, 1],
+        [1, 1, 1, 0, 1],
+    ];
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    struct Position(usize, usize);
+
+    impl DenseIndex for Position {
+        fn index(&self) -> usize {
+            self.0 * W + self.1
+        }
+    }
+
+    fn neighbors(p: &Position) -> Vec<(Position, usize)> {
+        [(0, 1), (2, 1), (1, 0), (1, 2)]
+            .into_iter()
+            .filter_map(|(dl, dc)| {
+                let line = (p.0 + dl).checked_sub(1)?;
+                let col = (p.1 + dc).checked_sub(1)?;
+                let cost = *GRID.get(line)?.get(col)?;
+                (cost != 0).then_some((Position(line, col), cost as usize))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_base() {
+        let starts = [Position(3, 0)];
+        let goal = |p: &Position| *p == Position(3, 4);
+        let expected = [
+            (3, 0),
+            (3, 1),
+            (3, 2),
+            (2, 2),
+            (1, 2),
+            (0, 2),
+            (0, 3),
+            (0, 4),
+            (1, 4),
+            (2, 4),
+            (3, 4),
+        ]
+        .map(|(line, col)| Position(line, col));
+
+        let path = dijkstra(starts, 20, neighbors, goal).unwrap();
+        assert_eq!(path.cost, 18);
+        assert_eq!(path.states, expected);
+
+        let path = dijkstra_buckets(starts, 20, neighbors, goal).unwrap();
+        assert_eq!(path.cost, 18);
+        assert_eq!(path.states, expected);
+
+        let manhattan = |p: &Position| 3 - p.0 + 4 - p.1;
+        let path = astar(starts, 20, neighbors, manhattan, goal).unwrap();
+        assert_eq!(path.cost, 18);
+        assert_eq!(path.states, expected);
+
+        // the start itself is a goal
+        let path = dijkstra(starts, 20, neighbors, |p| *p == Position(3, 0)).unwrap();
+        assert_eq!(path.cost, 0);
+        assert_eq!(path.states, vec![Position(3, 0)]);
+
+        // walls can't be reached
+        let wall = |p: &Position| *p == Position(3, 3);
+        assert_eq!(dijkstra_buckets(starts, 20, neighbors, wall), None);
+    }
+}
@@ -1,5 +1,7 @@
 use aoc_macro::declare_mods;
 
+// some building blocks aren't used by any day yet, they are covered by their own tests
+#[allow(dead_code)]
 mod helper;
 
 declare_mods!(25);
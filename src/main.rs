@@ -1,3 +1,5 @@
+// some building blocks aren't used by any day yet, they are covered by their own tests
+#[allow(dead_code)]
 pub mod helper;
 
 use std::{